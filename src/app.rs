@@ -1,7 +1,7 @@
-use crate::sox;
+use crate::{cache, editor::InsertSession, project::{self, AudioSource, Project}, registers::Registers, sox, swap, undo::{Change, Coalesce, UndoTree}, vim::RepeatableChange};
+use std::{fs, ops::Range, path::PathBuf, time::Instant};
 use serde::{Deserialize, Serialize};
 use arboard::Clipboard;
-use std::fs;
 
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum Mode {
@@ -9,7 +9,9 @@ pub enum Mode {
     Command,
     Insert,
     Adjust,
+    Split,
     Visual,
+    Cut,
 }
 
 pub enum AppEvent {
@@ -17,6 +19,14 @@ pub enum AppEvent {
     TranscriptionSuccess(Vec<Clip>, f64),
     TranscriptionFailure(String),
     StatusUpdate(String),
+    AutosaveTick,
+}
+
+/// A read-only popup for multi-line output such as `:cache list`. Any key
+/// closes it.
+pub struct InfoPanel {
+    pub title: String,
+    pub lines: Vec<String>,
 }
 
 pub enum AppState {
@@ -24,7 +34,16 @@ pub enum AppState {
     Ready,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Word {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    #[serde(default)]
+    pub confidence: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Clip {
     pub id: usize,
     pub speaker: String,
@@ -35,6 +54,81 @@ pub struct Clip {
     pub comment: String,
     #[serde(default)]
     pub is_manually_adjusted: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<Word>,
+    /// Spans of the original audio, in absolute seconds, that playback and
+    /// export skip because their words were cut from the transcript.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cuts: Vec<Cut>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cut {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Clip {
+    /// True when `words` lines up one-to-one with the whitespace-separated
+    /// words of `transcript`, so word timings can be trusted for edits.
+    pub fn has_word_timings(&self) -> bool {
+        !self.words.is_empty() && self.words.len() == self.transcript.split_whitespace().count()
+    }
+
+    pub fn offset_times(&mut self, offset: f64) {
+        self.start_time += offset;
+        self.end_time += offset;
+        for word in &mut self.words {
+            word.start += offset;
+            word.end += offset;
+        }
+        for cut in &mut self.cuts {
+            cut.start += offset;
+            cut.end += offset;
+        }
+    }
+
+    /// Adds a cut, merging it with any it overlaps or touches.
+    pub fn add_cut(&mut self, mut cut: Cut) {
+        self.cuts.retain(|other| {
+            let overlaps = other.start <= cut.end && cut.start <= other.end;
+            if overlaps {
+                cut.start = cut.start.min(other.start);
+                cut.end = cut.end.max(other.end);
+            }
+            !overlaps
+        });
+        let position = self.cuts.partition_point(|other| other.start < cut.start);
+        self.cuts.insert(position, cut);
+    }
+
+    /// The parts of `start_time..end_time` left after removing the cuts, in
+    /// order. Cuts outside the clip's window (e.g. after a nudge) are ignored.
+    pub fn audible_segments(&self) -> Vec<(f64, f64)> {
+        let mut cuts = self.cuts.clone();
+        cuts.sort_by(|a, b| a.start.total_cmp(&b.start));
+        let mut segments = Vec::new();
+        let mut position = self.start_time;
+        for cut in cuts {
+            if cut.end <= position || cut.start >= self.end_time {
+                continue;
+            }
+            if cut.start > position {
+                segments.push((position, cut.start));
+            }
+            position = cut.end;
+        }
+        if position < self.end_time {
+            segments.push((position, self.end_time));
+        }
+        segments
+    }
+
+    /// Seconds of the clip's window removed by cuts.
+    pub fn cut_seconds(&self) -> f64 {
+        let audible: f64 = self.audible_segments().iter().map(|(start, end)| end - start).sum();
+        (self.end_time - self.start_time - audible).max(0.0)
+    }
 }
 
 pub struct App {
@@ -46,10 +140,9 @@ pub struct App {
     pub mode: Mode,
     pub command_input: String,
     pub should_quit: bool,
-    pub register: Option<Clip>,
+    pub registers: Registers,
     pub status_message: String,
-    pub undo_stack: Vec<Vec<Clip>>,
-    pub redo_stack: Vec<Vec<Clip>>,
+    pub undo_tree: UndoTree,
     pub last_error: Option<String>,
     pub playback_pid: Option<u32>,
     pub adjust_word_index: usize,
@@ -57,6 +150,24 @@ pub struct App {
     pub debug_log: Vec<String>,
     pub total_time_discrepancy: f64,
     pub adjustments: Vec<usize>,
+    pub info_panel: Option<InfoPanel>,
+    pub audio_duration: f64,
+    pub project_created_at: Option<u64>,
+    /// Content hash of the audio the project was saved with, if known.
+    pub expected_audio_hash: Option<String>,
+    /// Bumped on every edit; the swap file is current when it has been
+    /// written at `swap_change_count == change_count`.
+    pub change_count: u64,
+    pub swap_change_count: u64,
+    pub last_swap_write: Option<Instant>,
+    pub pending_recovery: Option<PathBuf>,
+    /// The other end of the Visual mode selection; the cursor is the first.
+    pub visual_anchor: Option<usize>,
+    pub last_change: Option<RepeatableChange>,
+    pub insert: Option<InsertSession>,
+    /// The other end of the Cut mode word selection; `adjust_word_index`
+    /// is the cursor.
+    pub word_anchor: Option<usize>,
 }
 
 impl App {
@@ -70,10 +181,9 @@ impl App {
             mode: Mode::Normal,
             command_input: String::new(),
             should_quit: false,
-            register: None,
+            registers: Registers::default(),
             status_message: "Welcome to avim!".to_string(),
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
+            undo_tree: UndoTree::default(),
             last_error: None,
             playback_pid: None,
             adjust_word_index: 0,
@@ -81,6 +191,18 @@ impl App {
             debug_log: Vec::new(),
             total_time_discrepancy: 0.0,
             adjustments: Vec::new(),
+            info_panel: None,
+            audio_duration: 0.0,
+            project_created_at: None,
+            expected_audio_hash: None,
+            change_count: 0,
+            swap_change_count: 0,
+            last_swap_write: None,
+            pending_recovery: None,
+            visual_anchor: None,
+            last_change: None,
+            insert: None,
+            word_anchor: None,
         }
     }
 
@@ -90,15 +212,57 @@ impl App {
         }
     }
 
-    pub fn load_project(path: &str) -> Result<(String, Vec<Clip>), Box<dyn std::error::Error + Send + Sync>> {
-        let file_contents = fs::read_to_string(path)?;
-        let (audio_path, clips): (String, Vec<Clip>) = serde_json::from_str(&file_contents)?;
-        Ok((audio_path, clips))
+    /// Replaces `count` clips at `index` with `new_clips` as one undoable
+    /// step and returns the clips that were removed.
+    pub fn replace_clips(&mut self, index: usize, count: usize, new_clips: Vec<Clip>) -> Vec<Clip> {
+        let removed: Vec<Clip> = self.clips.splice(index..index + count, new_clips.iter().cloned()).collect();
+        self.record_change(Change { index, removed: removed.clone(), inserted: new_clips }, None);
+        removed
+    }
+
+    /// Edits a single clip in place. Repeated edits sharing a `coalesce` key
+    /// are undone together.
+    pub fn update_clip(&mut self, index: usize, coalesce: Option<Coalesce>, edit: impl FnOnce(&mut Clip)) {
+        let Some(clip) = self.clips.get_mut(index) else { return };
+        let before = clip.clone();
+        edit(clip);
+        let after = clip.clone();
+        self.record_change(Change { index, removed: vec![before], inserted: vec![after] }, coalesce);
+    }
+
+    /// Applies `edit` to every clip in `range` as one undoable step.
+    pub fn update_clips(&mut self, range: Range<usize>, edit: impl Fn(&mut Clip)) {
+        if range.end > self.clips.len() || range.is_empty() {
+            return;
+        }
+        let before = self.clips[range.clone()].to_vec();
+        self.clips[range.clone()].iter_mut().for_each(edit);
+        let after = self.clips[range.clone()].to_vec();
+        self.record_change(Change { index: range.start, removed: before, inserted: after }, None);
     }
 
-    pub fn save_undo_state(&mut self) {
-        self.undo_stack.push(self.clips.clone());
-        self.redo_stack.clear();
+    /// The clips covered by the Visual mode selection, or just the current
+    /// clip outside Visual mode.
+    pub fn selected_range(&self) -> Range<usize> {
+        let anchor = self.visual_anchor.unwrap_or(self.current_clip_index);
+        let start = anchor.min(self.current_clip_index);
+        let end = anchor.max(self.current_clip_index) + 1;
+        start..end.min(self.clips.len())
+    }
+
+    /// Swaps in a whole new set of clips, e.g. after autofix or recovery.
+    /// Only the clips that differ are kept in the undo log.
+    pub fn set_clips(&mut self, clips: Vec<Clip>) {
+        let change = Change::diff(&self.clips, &clips);
+        self.clips = clips;
+        self.record_change(change, None);
+    }
+
+    fn record_change(&mut self, change: Change, coalesce: Option<Coalesce>) {
+        if change.removed != change.inserted {
+            self.change_count += 1;
+        }
+        self.undo_tree.record(change, coalesce);
     }
 
     pub fn load_clips(&mut self, clips: Vec<Clip>, total_duration: f64) {
@@ -108,6 +272,7 @@ impl App {
         self.log_debug(format!("Total Transcript Duration: {:.2}s", transcription_duration));
         self.log_debug(format!("Discrepancy: {:.2}s", self.total_time_discrepancy));
         self.clips = clips;
+        self.audio_duration = total_duration;
         self.state = AppState::Ready;
         
         if self.total_time_discrepancy > 1.0 {
@@ -124,7 +289,7 @@ impl App {
     
     pub fn process_command(&mut self) {
         let parts: Vec<&str> = self.command_input.split_whitespace().collect();
-        if let Some(command) = parts.get(0) {
+        if let Some(command) = parts.first() {
             match *command {
                 "w" => self.save_project(parts.get(1).map(|s| s.to_string())),
                 "export" => {
@@ -144,7 +309,7 @@ impl App {
                 }
                 "q" | "q!" => self.should_quit = true,
                 "help" => {
-                    self.status_message = "Commands: :w, :export, :q, :autofix, :lasterror, :help".to_string();
+                    self.status_message = "Commands: :w, :export, :q, :autofix, :relink, :comment, :cache [info|list|clear], :earlier, :later, :undolist, :registers, :lasterror, :help".to_string();
                 }
                 "lasterror" => {
                     if let Some(err) = &self.last_error {
@@ -162,14 +327,112 @@ impl App {
                     }
                 }
                 "autofix" => crate::autofix::autofix_transcripts(self),
+                "relink" => match parts.get(1) {
+                    Some(path) => self.relink_audio(path.to_string()),
+                    None => self.status_message = "Usage: :relink <path to audio file>".to_string(),
+                },
+                "cache" => self.cache_command(parts.get(1).map(|s| s.to_string())),
+                "comment" => {
+                    let text = self.command_input.trim_start()["comment".len()..].trim().to_string();
+                    crate::vim::comment_selection(self, text);
+                }
+                "earlier" => crate::vim::time_travel(self, true, parts.get(1).map(|s| s.to_string())),
+                "later" => crate::vim::time_travel(self, false, parts.get(1).map(|s| s.to_string())),
+                "undolist" | "undol" => crate::vim::show_undo_list(self),
+                "registers" | "reg" | "display" | "di" => crate::vim::show_registers(self),
                 _ => self.status_message = format!("Unknown command: {}", self.command_input),
             }
         }
         self.command_input.clear();
         self.mode = Mode::Normal;
+        self.visual_anchor = None;
+    }
+
+    /// Points the project at a new location of its audio. If the content no
+    /// longer matches what the project was saved with, the relink still
+    /// happens but the user is warned that clip timings may be off.
+    fn relink_audio(&mut self, path: String) {
+        if !std::path::Path::new(&path).is_file() {
+            self.status_message = format!("File not found: {}", path);
+            return;
+        }
+        let new_hash = match cache::get_content_hash(&path) {
+            Ok(hash) => hash,
+            Err(e) => {
+                self.status_message = format!("Cannot read {}: {}", path, e);
+                return;
+            }
+        };
+
+        let matches = match self.expected_audio_hash.as_deref() {
+            Some(expected) if !expected.is_empty() => expected == new_hash,
+            _ => true,
+        };
+        self.original_audio_path = path.clone();
+        if let Some(duration) = sox::get_duration(&path) {
+            self.audio_duration = duration;
+        }
+        self.expected_audio_hash = Some(new_hash);
+        self.status_message = if matches {
+            format!("Relinked audio to {}. Use :w to save.", path)
+        } else {
+            format!("Relinked audio to {}, but its content differs from the original. Check clip timings, then :w.", path)
+        };
+    }
+
+    fn cache_command(&mut self, subcommand: Option<String>) {
+        match subcommand.as_deref().unwrap_or("info") {
+            "info" => {
+                let hash = match cache::get_content_hash(&self.original_audio_path) {
+                    Ok(hash) => hash,
+                    Err(e) => {
+                        self.status_message = format!("Cannot read audio file: {}", e);
+                        return;
+                    }
+                };
+                match cache::get_entry(&hash) {
+                    Some(record) => {
+                        self.info_panel = Some(InfoPanel {
+                            title: "Cache entry".to_string(),
+                            lines: vec![
+                                format!("Hash:    {}", hash),
+                                format!("Source:  {}", record.source_path),
+                                format!("Size:    {}", cache::format_size(record.size)),
+                                format!("Created: {}", cache::format_date(record.created)),
+                                format!("Backend: {}", record.backend),
+                                format!("Clips:   {}", record.clip_count),
+                            ],
+                        });
+                    }
+                    None => self.status_message = "No cache entry for this audio file.".to_string(),
+                }
+            }
+            "list" | "ls" => {
+                let entries = cache::list_entries();
+                if entries.is_empty() {
+                    self.status_message = "Cache is empty.".to_string();
+                } else {
+                    self.info_panel = Some(InfoPanel {
+                        title: format!("Cache ({} entries)", entries.len()),
+                        lines: entries.iter().map(|(hash, record)| cache::describe_entry(hash, record)).collect(),
+                    });
+                }
+            }
+            "clear" | "rm" => {
+                let removed = cache::get_content_hash(&self.original_audio_path)
+                    .and_then(|hash| cache::remove_entry(&hash));
+                self.status_message = match removed {
+                    Ok(true) => "Cache entry removed. The next launch will transcribe again.".to_string(),
+                    Ok(false) => "No cache entry for this audio file.".to_string(),
+                    Err(e) => format!("Failed to clear cache: {}", e),
+                };
+            }
+            other => self.status_message = format!("Unknown cache command: {}. Use :cache info|list|clear", other),
+        }
     }
 
     fn save_project(&mut self, new_path: Option<String>) {
+        let previous_swap = swap::get_swap_path(self);
         let path_to_save = match new_path {
             Some(p) => {
                 self.project_path = Some(p.clone());
@@ -179,15 +442,32 @@ impl App {
         };
 
         if let Some(path) = path_to_save {
-            let data_to_save = (self.original_audio_path.clone(), self.clips.clone());
-            match serde_json::to_string_pretty(&data_to_save) {
-                Ok(json_data) => {
-                    match fs::write(&path, json_data) {
-                        Ok(_) => self.status_message = format!("Project saved to {}", path),
-                        Err(e) => self.status_message = format!("Failed to save project: {}", e),
+            let mut audio = AudioSource::probe(&self.original_audio_path);
+            if audio.duration == 0.0 {
+                audio.duration = self.audio_duration;
+            }
+            if audio.hash.is_empty() {
+                audio.hash = self.expected_audio_hash.clone().unwrap_or_default();
+            }
+            let now = cache::now_secs();
+            let created_at = *self.project_created_at.get_or_insert(now);
+            let project = Project {
+                format_version: project::FORMAT_VERSION,
+                audio,
+                created_at,
+                modified_at: now,
+                clips: self.clips.clone(),
+                undo_history: Some(self.undo_tree.to_history()),
+            };
+            match project.save(&path) {
+                Ok(_) => {
+                    if let Some(previous_swap) = previous_swap {
+                        fs::remove_file(previous_swap).ok();
                     }
+                    swap::delete_swap(self);
+                    self.status_message = format!("Project saved to {}", path);
                 }
-                Err(e) => self.status_message = format!("Failed to serialize project data: {}", e),
+                Err(e) => self.status_message = format!("Failed to save project: {}", e),
             }
         } else {
             self.status_message = "No project file specified. Use :w <filename.avim>".to_string();
@@ -195,3 +475,80 @@ impl App {
     }
 }
 
+
+#[cfg(test)]
+impl Clip {
+    /// A clip for tests, with `transcript`'s words spread evenly over
+    /// `start..end`.
+    pub fn for_test(transcript: &str, start: f64, end: f64) -> Clip {
+        let texts: Vec<&str> = transcript.split_whitespace().collect();
+        let step = (end - start) / texts.len().max(1) as f64;
+        Clip {
+            id: 0,
+            speaker: "Speaker 1".to_string(),
+            transcript: transcript.to_string(),
+            start_time: start,
+            end_time: end,
+            comment: String::new(),
+            is_manually_adjusted: false,
+            words: texts.iter().enumerate().map(|(i, text)| Word {
+                text: text.to_string(),
+                start: start + step * i as f64,
+                end: start + step * (i + 1) as f64,
+                confidence: None,
+            }).collect(),
+            cuts: Vec::new(),
+        }
+    }
+}
+
+/// One test clip per transcript, each spanning `0..1s`.
+#[cfg(test)]
+pub fn test_clips(texts: &[&str]) -> Vec<Clip> {
+    texts.iter().map(|text| Clip::for_test(text, 0.0, 1.0)).collect()
+}
+
+#[cfg(test)]
+pub fn transcripts(clips: &[Clip]) -> Vec<&str> {
+    clips.iter().map(|c| c.transcript.as_str()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_cut_keeps_cuts_sorted_and_merged() {
+        let mut clip = Clip::for_test("a b c d e f", 0.0, 6.0);
+        clip.add_cut(Cut { start: 4.0, end: 5.0 });
+        clip.add_cut(Cut { start: 1.0, end: 2.0 });
+        assert_eq!(clip.cuts, vec![Cut { start: 1.0, end: 2.0 }, Cut { start: 4.0, end: 5.0 }]);
+
+        // Touching the first and overlapping the second merges all three.
+        clip.add_cut(Cut { start: 2.0, end: 4.5 });
+        assert_eq!(clip.cuts, vec![Cut { start: 1.0, end: 5.0 }]);
+    }
+
+    #[test]
+    fn audible_segments_skip_cuts() {
+        let mut clip = Clip::for_test("a b c d e f", 0.0, 6.0);
+        assert_eq!(clip.audible_segments(), vec![(0.0, 6.0)]);
+
+        clip.add_cut(Cut { start: 1.0, end: 2.0 });
+        clip.add_cut(Cut { start: 5.0, end: 6.0 });
+        assert_eq!(clip.audible_segments(), vec![(0.0, 1.0), (2.0, 5.0)]);
+        assert_eq!(clip.cut_seconds(), 2.0);
+    }
+
+    #[test]
+    fn audible_segments_ignore_cuts_outside_the_window() {
+        let mut clip = Clip::for_test("a b c d", 2.0, 6.0);
+        clip.add_cut(Cut { start: 0.0, end: 1.0 });
+        clip.add_cut(Cut { start: 1.5, end: 2.5 });
+        clip.add_cut(Cut { start: 7.0, end: 8.0 });
+        assert_eq!(clip.audible_segments(), vec![(2.5, 6.0)]);
+
+        clip.add_cut(Cut { start: 2.0, end: 6.0 });
+        assert!(clip.audible_segments().is_empty());
+    }
+}
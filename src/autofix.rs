@@ -1,4 +1,4 @@
-use crate::app::App;
+use crate::app::{App, Clip};
 
 pub fn enter_adjust_mode(app: &mut App) {
     if app.current_clip_index < app.clips.len() - 1 {
@@ -26,36 +26,38 @@ pub fn adjust_previous_word(app: &mut App) {
 }
 
 pub fn confirm_adjustment(app: &mut App) {
-    if app.current_clip_index >= app.clips.len() - 1 {
-        app.mode = crate::app::Mode::Normal;
+    app.mode = crate::app::Mode::Normal;
+    move_words_from_next_clip(app, app.adjust_word_index + 1);
+}
+
+/// Moves the first `words_to_move` words of the next clip onto the current
+/// one and records the adjustment for autofix. Used by Adjust mode and by
+/// `.` to repeat it.
+pub fn move_words_from_next_clip(app: &mut App, words_to_move: usize) {
+    if app.current_clip_index + 1 >= app.clips.len() {
+        app.status_message = "Cannot adjust the last clip.".to_string();
         return;
     }
-    
-    app.save_undo_state();
 
-    let next_clip_transcript = app.clips[app.current_clip_index + 1].transcript.clone();
-    let words: Vec<&str> = next_clip_transcript.split_whitespace().collect();
+    let index = app.current_clip_index;
+    let next_clip_words = app.clips[index + 1].transcript.split_whitespace().count();
+    if words_to_move == 0 || words_to_move > next_clip_words {
+        app.status_message = format!("The next clip has only {} words.", next_clip_words);
+        return;
+    }
 
-    if app.adjust_word_index < words.len() {
-        let words_to_move = app.adjust_word_index + 1;
-        let text_to_append = words.iter().take(words_to_move).map(|&s| s).collect::<Vec<&str>>().join(" ");
-        
-        app.adjustments.push(words_to_move);
-        app.log_debug(format!("Adjustment {}: Moved {} words.", app.adjustments.len(), words_to_move));
+    app.adjustments.push(words_to_move);
+    app.log_debug(format!("Adjustment {}: Moved {} words.", app.adjustments.len(), words_to_move));
 
-        let remaining_text = words.iter().skip(words_to_move).map(|&s| s).collect::<Vec<&str>>().join(" ");
-        
-        app.clips[app.current_clip_index].transcript.push_str(" ");
-        app.clips[app.current_clip_index].transcript.push_str(&text_to_append);
-        app.clips[app.current_clip_index + 1].transcript = remaining_text;
-        
-        // Mark both clips as manually adjusted
-        app.clips[app.current_clip_index].is_manually_adjusted = true;
-        app.clips[app.current_clip_index + 1].is_manually_adjusted = true;
-    }
+    let mut pair = app.clips[index..index + 2].to_vec();
+    move_words_to_previous_clip(&mut pair, 0, words_to_move);
+
+    // Mark both clips as manually adjusted
+    pair[0].is_manually_adjusted = true;
+    pair[1].is_manually_adjusted = true;
+    app.replace_clips(index, 2, pair);
+    app.last_change = Some(crate::vim::RepeatableChange::MoveWords { count: words_to_move });
 
-    app.mode = crate::app::Mode::Normal;
-    
     if app.adjustments.len() < 3 {
          app.status_message = format!("Adjustment learned. Adjust {} more to find a pattern.", 3 - app.adjustments.len());
     } else {
@@ -63,6 +65,146 @@ pub fn confirm_adjustment(app: &mut App) {
     }
 }
 
+pub fn enter_split_mode(app: &mut App) {
+    let word_count = app.clips.get(app.current_clip_index).map_or(0, |c| c.transcript.split_whitespace().count());
+    if word_count >= 2 {
+        app.mode = crate::app::Mode::Split;
+        app.adjust_word_index = 0;
+        app.status_message = "SPLIT MODE: Use 'w'/'b' to pick the last word of the first half, 'Enter' to split, 'Esc' to cancel.".to_string();
+    } else {
+        app.status_message = "Need at least two words to split a clip.".to_string();
+    }
+}
+
+pub fn split_next_word(app: &mut App) {
+    if let Some(clip) = app.clips.get(app.current_clip_index) {
+        // Leave at least one word for the second clip.
+        if app.adjust_word_index + 2 < clip.transcript.split_whitespace().count() {
+            app.adjust_word_index += 1;
+        }
+    }
+}
+
+pub fn confirm_split(app: &mut App) {
+    app.mode = crate::app::Mode::Normal;
+    split_current_clip(app, app.adjust_word_index + 1);
+}
+
+/// Splits the current clip after its first `words_in_first` words. The new
+/// boundary is the end of the last word kept, as for Adjust mode. Used by
+/// Split mode and by `.` to repeat it.
+pub fn split_current_clip(app: &mut App, words_in_first: usize) {
+    let index = app.current_clip_index;
+    let Some(clip) = app.clips.get(index) else { return };
+    let word_count = clip.transcript.split_whitespace().count();
+    if words_in_first == 0 || words_in_first >= word_count {
+        app.status_message = format!("Cannot split a {}-word clip after word {}.", word_count, words_in_first);
+        return;
+    }
+
+    let next_id = app.clips.iter().map(|c| c.id).max().unwrap_or(0) + 1;
+    let (mut first, mut second) = split_clip(clip, words_in_first);
+    second.id = next_id;
+    first.is_manually_adjusted = true;
+    second.is_manually_adjusted = true;
+    app.replace_clips(index, 1, vec![first, second]);
+    app.last_change = Some(crate::vim::RepeatableChange::Split { count: words_in_first });
+    app.status_message = format!("Clip split after word {}.", words_in_first);
+}
+
+/// Divides `clip` after its first `count` words, carrying word timings and
+/// placing the audio boundary where the last kept word ends. The comment
+/// stays with the first half.
+fn split_clip(clip: &Clip, count: usize) -> (Clip, Clip) {
+    let words: Vec<&str> = clip.transcript.split_whitespace().collect();
+    let boundary = estimate_boundary(clip, count).clamp(clip.start_time, clip.end_time);
+    let has_timings = clip.has_word_timings();
+
+    let mut first = clip.clone();
+    first.transcript = words[..count].join(" ");
+    first.end_time = boundary;
+
+    let mut second = clip.clone();
+    second.transcript = words[count..].join(" ");
+    second.start_time = boundary;
+    second.comment.clear();
+    first.cuts.retain(|cut| cut.start < boundary);
+    second.cuts.retain(|cut| cut.end > boundary);
+
+    if has_timings {
+        first.words.truncate(count);
+        second.words.drain(..count);
+    } else {
+        first.words.clear();
+        second.words.clear();
+    }
+    (first, second)
+}
+
+/// Moves the first `count` words of `clips[index + 1]` onto the end of
+/// `clips[index]`, carrying their word timings along when both clips have
+/// them. If the receiving clip had no usable timings its word list is
+/// dropped rather than left half-filled.
+///
+/// The audio boundary between the two clips moves with the text: it becomes
+/// the end of the last moved word, or an estimate from the next clip's
+/// speaking rate when there are no word timings.
+fn move_words_to_previous_clip(clips: &mut [Clip], index: usize, count: usize) {
+    let next_transcript = clips[index + 1].transcript.clone();
+    let words: Vec<&str> = next_transcript.split_whitespace().collect();
+    let text_to_append = words.iter().take(count).copied().collect::<Vec<_>>().join(" ");
+    let remaining_text = words.iter().skip(count).copied().collect::<Vec<_>>().join(" ");
+
+    let next_has_timings = clips[index + 1].has_word_timings();
+    let boundary = estimate_boundary(&clips[index + 1], count);
+    let current_has_timings = clips[index].has_word_timings() || clips[index].transcript.trim().is_empty();
+    let moved_words = if next_has_timings {
+        clips[index + 1].words.drain(..count.min(words.len())).collect()
+    } else {
+        clips[index + 1].words.clear();
+        Vec::new()
+    };
+
+    let current = &mut clips[index];
+    if !current.transcript.is_empty() {
+        current.transcript.push(' ');
+    }
+    current.transcript.push_str(&text_to_append);
+    if next_has_timings && current_has_timings {
+        current.words.extend(moved_words);
+    } else {
+        current.words.clear();
+    }
+    clips[index + 1].transcript = remaining_text;
+    // Cuts apply wherever the boundary ends up; each clip only plays the
+    // ones inside its own window.
+    let moved_cuts = clips[index + 1].cuts.clone();
+    for cut in moved_cuts {
+        clips[index].add_cut(cut);
+    }
+
+    if boundary > clips[index].start_time && boundary <= clips[index + 1].end_time {
+        clips[index].end_time = boundary;
+        clips[index + 1].start_time = boundary;
+    }
+}
+
+/// Time at which the first `count` words of `clip` have been spoken.
+fn estimate_boundary(clip: &Clip, count: usize) -> f64 {
+    if count == 0 {
+        return clip.start_time;
+    }
+    if clip.has_word_timings() {
+        return clip.words[count.min(clip.words.len()) - 1].end;
+    }
+    let word_count = clip.transcript.split_whitespace().count();
+    if word_count == 0 {
+        return clip.start_time;
+    }
+    let seconds_per_word = (clip.end_time - clip.start_time) / word_count as f64;
+    clip.start_time + seconds_per_word * count.min(word_count) as f64
+}
+
 pub fn autofix_transcripts(app: &mut App) {
     if app.adjustments.is_empty() {
         app.status_message = "Not enough data to autofix. Please adjust a few clips first.".to_string();
@@ -86,32 +228,108 @@ pub fn autofix_transcripts(app: &mut App) {
         return;
     }
 
-    app.save_undo_state();
+    let mut clips = app.clips.clone();
     let words_to_move_avg = mean.round() as usize;
     app.log_debug(format!("Applying autofix, moving avg {} words.", words_to_move_avg));
     let mut total_moved = 0;
 
-    for i in (0..app.clips.len() - 1).rev() {
+    for i in (0..clips.len() - 1).rev() {
         // Check if the clip has been manually adjusted
-        if app.clips[i].is_manually_adjusted || app.clips[i + 1].is_manually_adjusted {
+        if clips[i].is_manually_adjusted || clips[i + 1].is_manually_adjusted {
             continue;
         }
 
-        let next_clip_transcript = app.clips[i + 1].transcript.clone();
+        let next_clip_transcript = clips[i + 1].transcript.clone();
         let next_clip_words: Vec<&str> = next_clip_transcript.split_whitespace().collect();
         
         if next_clip_words.len() > words_to_move_avg {
-            let text_to_append = next_clip_words.iter().take(words_to_move_avg).map(|&s| s).collect::<Vec<_>>().join(" ");
-            let remaining_text = next_clip_words.iter().skip(words_to_move_avg).map(|&s| s).collect::<Vec<_>>().join(" ");
-
-            app.clips[i].transcript.push_str(" ");
-            app.clips[i].transcript.push_str(&text_to_append);
-            app.clips[i + 1].transcript = remaining_text;
+            move_words_to_previous_clip(&mut clips, i, words_to_move_avg);
             total_moved += words_to_move_avg;
         }
     }
     
-    app.clips.retain(|clip| !clip.transcript.trim().is_empty());
+    clips.retain(|clip| !clip.transcript.trim().is_empty());
+    app.set_clips(clips);
+    app.current_clip_index = app.current_clip_index.min(app.clips.len().saturating_sub(1));
     app.status_message = format!("Autofix complete. Moved approx {} words.", total_moved);
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word_texts(clip: &Clip) -> Vec<&str> {
+        clip.words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    #[test]
+    fn moving_the_first_word_shifts_the_boundary_to_its_end() {
+        let mut clips = vec![Clip::for_test("a b", 0.0, 2.0), Clip::for_test("c d e f", 2.0, 6.0)];
+        move_words_to_previous_clip(&mut clips, 0, 1);
+        assert_eq!(clips[0].transcript, "a b c");
+        assert_eq!(clips[1].transcript, "d e f");
+        assert_eq!(word_texts(&clips[0]), vec!["a", "b", "c"]);
+        assert_eq!(word_texts(&clips[1]), vec!["d", "e", "f"]);
+        assert_eq!((clips[0].end_time, clips[1].start_time), (3.0, 3.0));
+    }
+
+    #[test]
+    fn moving_every_word_empties_the_next_clip() {
+        let mut clips = vec![Clip::for_test("a", 0.0, 1.0), Clip::for_test("b c", 1.0, 3.0)];
+        move_words_to_previous_clip(&mut clips, 0, 2);
+        assert_eq!(clips[0].transcript, "a b c");
+        assert_eq!(clips[1].transcript, "");
+        assert!(clips[1].words.is_empty());
+        assert_eq!((clips[0].end_time, clips[1].start_time), (3.0, 3.0));
+    }
+
+    #[test]
+    fn clips_without_word_timings_use_the_speaking_rate() {
+        let mut clips = vec![Clip::for_test("a b", 0.0, 2.0), Clip::for_test("c d e f", 2.0, 6.0)];
+        clips[1].words.clear();
+        assert_eq!(estimate_boundary(&clips[1], 0), 2.0);
+        assert_eq!(estimate_boundary(&clips[1], 3), 5.0);
+        assert_eq!(estimate_boundary(&clips[1], 10), 6.0);
+
+        move_words_to_previous_clip(&mut clips, 0, 3);
+        assert_eq!(clips[0].transcript, "a b c d e");
+        // The receiving clip can no longer line its words up with timings.
+        assert!(clips[0].words.is_empty());
+        assert_eq!((clips[0].end_time, clips[1].start_time), (5.0, 5.0));
+    }
+
+    #[test]
+    fn boundary_uses_word_timings_when_present() {
+        let clip = Clip::for_test("a b c d", 2.0, 6.0);
+        assert_eq!(estimate_boundary(&clip, 2), 4.0);
+        assert_eq!(estimate_boundary(&clip, 9), 6.0);
+    }
+
+    #[test]
+    fn splitting_carries_word_timings_and_keeps_the_comment_first() {
+        let mut clip = Clip::for_test("a b c d", 2.0, 6.0);
+        clip.comment = "note".to_string();
+        let (first, second) = split_clip(&clip, 1);
+        assert_eq!((first.transcript.as_str(), second.transcript.as_str()), ("a", "b c d"));
+        assert_eq!((first.end_time, second.start_time), (3.0, 3.0));
+        assert_eq!(word_texts(&first), vec!["a"]);
+        assert_eq!(word_texts(&second), vec!["b", "c", "d"]);
+        assert_eq!((first.comment.as_str(), second.comment.as_str()), ("note", ""));
+    }
+
+    #[test]
+    fn splitting_before_the_first_or_after_the_last_word_is_refused() {
+        let mut app = App::new("audio.wav".to_string(), None, false);
+        app.clips = vec![Clip::for_test("a b c", 0.0, 3.0)];
+        for words_in_first in [0, 3, 4] {
+            split_current_clip(&mut app, words_in_first);
+            assert!(app.status_message.starts_with("Cannot split"), "{}", words_in_first);
+            assert_eq!(app.clips.len(), 1);
+        }
+
+        split_current_clip(&mut app, 2);
+        assert_eq!(crate::app::transcripts(&app.clips), vec!["a b", "c"]);
+        assert_ne!(app.clips[0].id, app.clips[1].id);
+    }
+}
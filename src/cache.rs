@@ -1,12 +1,16 @@
 use std::{
+    collections::HashMap,
     error::Error,
-    fs,
+    fs::{self, File},
+    io::Read,
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use crate::app::Clip;
 
-fn get_cache_dir() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+pub fn get_cache_dir() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
     let cache_dir = dirs::cache_dir()
         .ok_or("Could not find cache directory")?
         .join("avim"); // Renamed from audiovim
@@ -14,33 +18,404 @@ fn get_cache_dir() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
     Ok(cache_dir)
 }
 
-fn get_cache_path_for_file(audio_path: &str) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+/// Identifies a transcript: what audio was transcribed, and how.
+///
+/// `content_hash` is a SHA-256 of the audio bytes, so renaming or moving a
+/// file keeps its transcript while editing it in place does not.
+/// `fingerprint` names the backend, model and prompt; a cached transcript
+/// made with a different fingerprint is ignored.
+#[derive(Clone, Debug)]
+pub struct CacheKey {
+    pub content_hash: String,
+    pub fingerprint: String,
+    pub source_path: String,
+}
+
+impl CacheKey {
+    pub fn for_file(audio_path: &str, fingerprint: String) -> Result<CacheKey, Box<dyn Error + Send + Sync>> {
+        Ok(CacheKey {
+            content_hash: get_content_hash(audio_path)?,
+            fingerprint,
+            source_path: audio_path.to_string(),
+        })
+    }
+}
+
+/// On-disk cache file: the clips plus the fingerprint they were made with.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    fingerprint: String,
+    clips: Vec<Clip>,
+}
+
+/// Remembers the content hash of each file by path, size and modification
+/// time so unchanged files don't have to be re-read on every launch.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+struct HashRecord {
+    size: u64,
+    modified: u64,
+    content_hash: String,
+}
+
+fn get_hash_index_path() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    Ok(get_cache_dir()?.join("hashes.json"))
+}
+
+pub fn get_content_hash(audio_path: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
     let absolute_path = Path::new(audio_path).canonicalize()?;
+    let path_key = absolute_path.to_string_lossy().to_string();
+    let metadata = fs::metadata(&absolute_path)?;
+    let size = metadata.len();
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_secs();
+
+    let index_path = get_hash_index_path()?;
+    let mut index: HashMap<String, HashRecord> = fs::read_to_string(&index_path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    if let Some(record) = index.get(&path_key) {
+        if record.size == size && record.modified == modified {
+            return Ok(record.content_hash.clone());
+        }
+    }
+
+    let mut file = File::open(&absolute_path)?;
     let mut hasher = Sha256::new();
-    hasher.update(absolute_path.to_str().unwrap().as_bytes());
-    let result = hasher.finalize();
-    let hash_hex = hex::encode(result);
-    let cache_filename = format!("{}.json", hash_hex);
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 { break; }
+        hasher.update(&buffer[..read]);
+    }
+    let content_hash = hex::encode(hasher.finalize());
+
+    index.insert(path_key, HashRecord { size, modified, content_hash: content_hash.clone() });
+    fs::write(index_path, serde_json::to_string(&index)?)?;
+    Ok(content_hash)
+}
+
+fn get_cache_path(key: &CacheKey) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let cache_filename = format!("{}.json", key.content_hash);
     Ok(get_cache_dir()?.join(cache_filename))
 }
 
-pub async fn load_from_cache(audio_path: &str) -> Option<Vec<Clip>> {
-    if let Ok(cache_path) = get_cache_path_for_file(audio_path) {
-        if cache_path.exists() {
-            if let Ok(file_contents) = fs::read_to_string(cache_path) {
-                if let Ok(clips) = serde_json::from_str(&file_contents) {
-                    return Some(clips);
-                }
+/// Partial results live in `chunks/<content hash>/` until the whole file has
+/// been transcribed, so an interrupted run can pick up where it stopped.
+fn get_chunk_cache_dir(key: &CacheKey) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    Ok(get_cache_dir()?.join("chunks").join(&key.content_hash))
+}
+
+/// Chunk results hold clips already shifted to absolute times, so the file
+/// name pins the chunk's window: a different start (e.g. after changing
+/// `silence_search_seconds`) must not reuse them.
+fn get_chunk_cache_path(key: &CacheKey, chunk_index: usize, chunk_start: f64, chunk_duration: f64) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let chunk_filename = format!(
+        "{}_{}ms_{}ms.json",
+        chunk_index,
+        (chunk_start * 1000.0).round() as u64,
+        (chunk_duration * 1000.0).round() as u64,
+    );
+    Ok(get_chunk_cache_dir(key)?.join(chunk_filename))
+}
+
+fn read_entry(path: &Path, key: &CacheKey) -> Option<Vec<Clip>> {
+    let file_contents = fs::read_to_string(path).ok()?;
+    let entry: CacheEntry = serde_json::from_str(&file_contents).ok()?;
+    if entry.fingerprint == key.fingerprint {
+        Some(entry.clips)
+    } else {
+        None
+    }
+}
+
+fn write_entry(path: &Path, key: &CacheKey, clips: &[Clip]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let entry = CacheEntry { fingerprint: key.fingerprint.clone(), clips: clips.to_vec() };
+    fs::write(path, serde_json::to_string_pretty(&entry)?)?;
+    Ok(())
+}
+
+pub async fn load_from_cache(key: &CacheKey) -> Option<Vec<Clip>> {
+    read_entry(&get_cache_path(key).ok()?, key)
+}
+
+pub async fn save_to_cache(key: &CacheKey, clips: &[Clip]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    write_entry(&get_cache_path(key)?, key, clips)?;
+
+    let source_path = Path::new(&key.source_path);
+    let mut index = load_index();
+    index.insert(key.content_hash.clone(), IndexRecord {
+        source_path: source_path.canonicalize().unwrap_or(source_path.to_path_buf()).to_string_lossy().to_string(),
+        size: fs::metadata(source_path).map(|m| m.len()).unwrap_or(0),
+        created: now_secs(),
+        backend: key.fingerprint.clone(),
+        clip_count: clips.len(),
+    });
+    save_index(&index)
+}
+
+pub async fn load_chunk_from_cache(key: &CacheKey, chunk_index: usize, chunk_start: f64, chunk_duration: f64) -> Option<Vec<Clip>> {
+    read_entry(&get_chunk_cache_path(key, chunk_index, chunk_start, chunk_duration).ok()?, key)
+}
+
+pub async fn save_chunk_to_cache(key: &CacheKey, chunk_index: usize, chunk_start: f64, chunk_duration: f64, clips: &[Clip]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    fs::create_dir_all(get_chunk_cache_dir(key)?)?;
+    write_entry(&get_chunk_cache_path(key, chunk_index, chunk_start, chunk_duration)?, key, clips)
+}
+
+/// Removes the per-chunk results once they have been merged into the full
+/// transcript cache.
+pub async fn clear_chunk_cache(key: &CacheKey) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chunk_dir = get_chunk_cache_dir(key)?;
+    if chunk_dir.exists() {
+        fs::remove_dir_all(chunk_dir)?;
+    }
+    Ok(())
+}
+
+/// What `index.json` records about each cached transcript, keyed by content
+/// hash, so the cache can be inspected without opening every entry.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IndexRecord {
+    pub source_path: String,
+    pub size: u64,
+    pub created: u64,
+    pub backend: String,
+    pub clip_count: usize,
+}
+
+fn get_index_path() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    Ok(get_cache_dir()?.join("index.json"))
+}
+
+fn load_index() -> HashMap<String, IndexRecord> {
+    get_index_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(index: &HashMap<String, IndexRecord>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    fs::write(get_index_path()?, serde_json::to_string_pretty(index)?)?;
+    Ok(())
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// All indexed transcripts, newest first.
+pub fn list_entries() -> Vec<(String, IndexRecord)> {
+    let mut entries: Vec<_> = load_index().into_iter().collect();
+    entries.sort_by_key(|(_, record)| std::cmp::Reverse(record.created));
+    entries
+}
+
+pub fn get_entry(content_hash: &str) -> Option<IndexRecord> {
+    load_index().get(content_hash).cloned()
+}
+
+/// Deletes the transcript, any leftover chunk results and the index record
+/// for `content_hash`. Returns whether anything was there to delete.
+pub fn remove_entry(content_hash: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let cache_dir = get_cache_dir()?;
+    let mut removed = false;
+
+    let cache_path = cache_dir.join(format!("{}.json", content_hash));
+    if cache_path.exists() {
+        fs::remove_file(cache_path)?;
+        removed = true;
+    }
+    let chunk_dir = cache_dir.join("chunks").join(content_hash);
+    if chunk_dir.exists() {
+        fs::remove_dir_all(chunk_dir)?;
+        removed = true;
+    }
+    let mut index = load_index();
+    if index.remove(content_hash).is_some() {
+        save_index(&index)?;
+        removed = true;
+    }
+    Ok(removed)
+}
+
+/// Removes transcripts and chunk directories older than `max_age_secs`.
+/// Unindexed files (e.g. from older avim versions) are aged by their
+/// modification time. Returns the number of entries removed.
+pub fn collect_garbage(max_age_secs: u64) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let cache_dir = get_cache_dir()?;
+    let cutoff = now_secs().saturating_sub(max_age_secs);
+    let index = load_index();
+    let modified_secs = |path: &Path| -> u64 {
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    };
+
+    let mut stale = Vec::new();
+    for dir_entry in fs::read_dir(&cache_dir)?.flatten() {
+        let path = dir_entry.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+        let Some(hash) = name.strip_suffix(".json") else { continue };
+        if hash == "index" || hash == "hashes" {
+            continue;
+        }
+        let age = index.get(hash).map_or_else(|| modified_secs(&path), |r| r.created);
+        if age < cutoff {
+            stale.push(hash.to_string());
+        }
+    }
+    if let Ok(chunk_dirs) = fs::read_dir(cache_dir.join("chunks")) {
+        for dir_entry in chunk_dirs.flatten() {
+            let hash = dir_entry.file_name().to_string_lossy().to_string();
+            if modified_secs(&dir_entry.path()) < cutoff && !stale.contains(&hash) {
+                stale.push(hash);
             }
         }
     }
-    None
+    for hash in index.keys() {
+        if index[hash].created < cutoff && !stale.contains(hash) {
+            stale.push(hash.clone());
+        }
+    }
+
+    for hash in &stale {
+        remove_entry(hash)?;
+    }
+    Ok(stale.len())
 }
 
-pub async fn save_to_cache(audio_path: &str, clips: &[Clip]) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let cache_path = get_cache_path_for_file(audio_path)?;
-    let json_data = serde_json::to_string_pretty(clips)?;
-    fs::write(cache_path, json_data)?;
+/// Parses ages such as `30d`, `12h`, `2w`, `90m` or `45s` into seconds.
+/// The unit is required, so a bare `30` is rejected rather than guessed.
+pub fn parse_age(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().ok()?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    number.checked_mul(multiplier)
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD` (UTC).
+pub fn format_date(secs: u64) -> String {
+    // Civil-from-days, see https://howardhinnant.github.io/date_algorithms.html
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+pub fn format_size(bytes: u64) -> String {
+    let mb = bytes as f64 / (1024.0 * 1024.0);
+    if mb >= 1.0 { format!("{:.1}M", mb) } else { format!("{}K", bytes / 1024) }
+}
+
+/// One line describing a cache entry, as shown by `avim cache ls` and `:cache`.
+pub fn describe_entry(content_hash: &str, record: &IndexRecord) -> String {
+    format!(
+        "{}  {:>7}  {}  {:>5} clips  {:<28}  {}",
+        &content_hash[..12.min(content_hash.len())],
+        format_size(record.size),
+        format_date(record.created),
+        record.clip_count,
+        record.backend,
+        record.source_path
+    )
+}
+
+/// Handles `avim cache ls|rm|gc`.
+pub fn run_cli(args: &[String]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match args.first().map(String::as_str) {
+        Some("ls") | Some("list") => {
+            let entries = list_entries();
+            if entries.is_empty() {
+                println!("Cache is empty.");
+            }
+            for (hash, record) in entries {
+                println!("{}", describe_entry(&hash, &record));
+            }
+        }
+        Some("rm") => {
+            if args.len() < 2 {
+                return Err("Usage: avim cache rm <hash prefix | audio file>...".into());
+            }
+            for target in &args[1..] {
+                let matches: Vec<String> = if Path::new(target).is_file() {
+                    vec![get_content_hash(target)?]
+                } else {
+                    load_index().into_keys().filter(|hash| hash.starts_with(target.as_str())).collect()
+                };
+                match matches.len() {
+                    0 => println!("{}: no cache entry found", target),
+                    1 => {
+                        if remove_entry(&matches[0])? {
+                            println!("Removed {}", &matches[0][..12]);
+                        } else {
+                            println!("{}: no cache entry found", target);
+                        }
+                    }
+                    n => println!("{}: ambiguous, matches {} entries", target, n),
+                }
+            }
+        }
+        Some("gc") => {
+            let max_age = match (args.get(1).map(String::as_str), args.get(2)) {
+                (Some("--older-than"), Some(age)) => parse_age(age).ok_or(format!("Invalid age '{}', use a number with a unit (s, m, h, d or w), e.g. 30d", age))?,
+                (None, _) => parse_age("30d").unwrap(),
+                _ => return Err("Usage: avim cache gc [--older-than <age>]".into()),
+            };
+            let removed = collect_garbage(max_age)?;
+            println!("Removed {} cache entr{}.", removed, if removed == 1 { "y" } else { "ies" });
+        }
+        _ => return Err("Usage: avim cache ls | rm <hash prefix | audio file>... | gc [--older-than <age>]".into()),
+    }
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages_need_a_unit() {
+        assert_eq!(parse_age("7d"), Some(7 * 24 * 60 * 60));
+        assert_eq!(parse_age("12h"), Some(12 * 60 * 60));
+        assert_eq!(parse_age(" 90m "), Some(90 * 60));
+        assert_eq!(parse_age("0s"), Some(0));
+        assert_eq!(parse_age("0"), None);
+        assert_eq!(parse_age("30"), None);
+        assert_eq!(parse_age("5y"), None);
+        assert_eq!(parse_age("d"), None);
+        assert_eq!(parse_age("-1d"), None);
+    }
+
+    #[test]
+    fn ages_that_overflow_are_rejected() {
+        assert_eq!(parse_age("30000000000000000w"), None);
+        assert_eq!(parse_age("99999999999999999999s"), None);
+    }
+
+    #[test]
+    fn dates_are_formatted_in_utc() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_709_251_199), "2024-02-29");
+        assert_eq!(format_date(1_735_689_600), "2025-01-01");
+        // Far-future timestamps still format rather than overflow.
+        assert_eq!(format_date(u64::MAX), "584554051223-11-09");
+    }
+}
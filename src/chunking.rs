@@ -0,0 +1,320 @@
+use std::{
+    env, fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    process::Command,
+    sync::{mpsc, Semaphore},
+    task::JoinSet,
+};
+use crate::{
+    app::{AppEvent, Clip},
+    cache::{self, CacheKey},
+    config::Config,
+    sox,
+    stitch::{self, ChunkResult},
+    transcriber::{ChunkContext, TranscribeError, Transcriber},
+};
+
+pub const CHUNK_DURATION_SECONDS: f64 = 300.0;
+const RETRY_BASE_DELAY_SECONDS: f64 = 1.0;
+const RETRY_MAX_DELAY_SECONDS: f64 = 60.0;
+
+/// Splits `path` into roughly fixed-length chunks and transcribes up to
+/// `config.concurrency` of them at a time. Each chunk window extends
+/// `config.chunk_overlap_seconds` into the next one so that sentences cut at
+/// a boundary are heard whole by at least one chunk. Chunks may finish in
+/// any order; the clips are stitched back together by chunk index before
+/// sanitizing them against the true audio duration.
+///
+/// With a `cache_key`, every finished chunk is cached on its own. With
+/// `use_cache` also set, chunks left over from an earlier, interrupted run
+/// are reused and only the missing ones are sent to the backend.
+pub async fn transcribe_file(
+    path: &str,
+    total_duration: f64,
+    transcriber: Arc<dyn Transcriber>,
+    config: &Config,
+    cache_key: Option<CacheKey>,
+    use_cache: bool,
+    tx: &mpsc::Sender<AppEvent>,
+) -> Result<Vec<Clip>, String> {
+    let max_attempts = config.max_attempts;
+    let overlap_seconds = config.chunk_overlap_seconds.max(0.0);
+    tx.send(AppEvent::StatusUpdate("Finding quiet points to split the audio...".to_string())).await.ok();
+    let boundaries = plan_chunk_boundaries(path, total_duration, config.silence_search_seconds).await;
+    let num_chunks = boundaries.len() - 1;
+    let semaphore = Arc::new(Semaphore::new(config.concurrency.max(1)));
+    let progress = Arc::new(Progress {
+        total: num_chunks,
+        backend: transcriber.name().to_string(),
+        done: AtomicUsize::new(0),
+        cached: AtomicUsize::new(0),
+        in_flight: AtomicUsize::new(0),
+    });
+    let mut tasks = JoinSet::new();
+    let mut contexts = Vec::with_capacity(num_chunks);
+
+    for i in 0..num_chunks {
+        let chunk_start_time = boundaries[i];
+        let context = ChunkContext {
+            index: i,
+            total_chunks: num_chunks,
+            start_time: chunk_start_time,
+            duration: (boundaries[i + 1] + overlap_seconds).min(total_duration) - chunk_start_time,
+        };
+        contexts.push(context);
+        let semaphore = semaphore.clone();
+        let progress = progress.clone();
+        let transcriber = transcriber.clone();
+        let path = path.to_string();
+        let tx = tx.clone();
+        let cache_key = cache_key.clone();
+
+        tasks.spawn(async move {
+            if let (true, Some(key)) = (use_cache, &cache_key) {
+                if let Some(clips) = cache::load_chunk_from_cache(key, i, context.start_time, context.duration).await {
+                    progress.cached(&tx).await;
+                    return Ok((i, clips));
+                }
+            }
+            let _permit = semaphore.acquire_owned().await.map_err(|e| e.to_string())?;
+            progress.start(&tx).await;
+            let result = transcribe_one(&path, total_duration, transcriber.as_ref(), context, max_attempts, &tx).await;
+            progress.finish(&tx, result.is_ok()).await;
+            if let (Ok(clips), Some(key)) = (&result, &cache_key) {
+                cache::save_chunk_to_cache(key, i, context.start_time, context.duration, clips).await.ok();
+            }
+            result.map(|clips| (i, clips))
+        });
+    }
+
+    let mut chunk_results: Vec<Option<Vec<Clip>>> = vec![None; num_chunks];
+    progress.report(tx).await;
+
+    // On the first failure, stop starting new chunks but let the ones in
+    // flight finish so their results still land in the chunk cache.
+    let mut first_error = None;
+    while let Some(joined) = tasks.join_next().await {
+        let error = match joined {
+            Ok(Ok((i, clips))) => {
+                chunk_results[i] = Some(clips);
+                continue;
+            }
+            Ok(Err(e)) => e,
+            Err(e) => format!("Transcription task failed: {}", e),
+        };
+        if first_error.is_none() {
+            first_error = Some(error);
+            semaphore.close();
+        }
+    }
+    if let Some(e) = first_error {
+        let finished = chunk_results.iter().filter(|r| r.is_some()).count();
+        if finished > 0 {
+            return Err(format!("{} ({}/{} chunks are cached; run avim again to resume)", e, finished, num_chunks));
+        }
+        return Err(e);
+    }
+
+    let chunks = contexts.iter().zip(chunk_results)
+        .map(|(context, clips)| ChunkResult {
+            window_start: context.start_time,
+            window_end: context.start_time + context.duration,
+            clips: clips.unwrap_or_default(),
+        })
+        .collect();
+
+    let sanitized_clips = stitch::stitch_chunks(chunks).into_iter()
+        .filter(|clip| clip.start_time < total_duration)
+        .map(|mut clip| {
+            if clip.end_time > total_duration { clip.end_time = total_duration; }
+            clip
+        })
+        .collect();
+    Ok(sanitized_clips)
+}
+
+/// Returns the chunk start times followed by `total_duration`. Each cut
+/// lands on the quietest point within `search_seconds` of its multiple of
+/// `CHUNK_DURATION_SECONDS`, so words are not split mid-syllable. A search
+/// radius of zero, or a failed analysis, keeps the exact multiple. SoX runs
+/// on the blocking thread pool so the analysis doesn't stall the runtime.
+async fn plan_chunk_boundaries(path: &str, total_duration: f64, search_seconds: f64) -> Vec<f64> {
+    let num_chunks = (total_duration / CHUNK_DURATION_SECONDS).ceil() as usize;
+    let search_radius = search_seconds.clamp(0.0, CHUNK_DURATION_SECONDS / 2.0);
+    let mut boundaries = vec![0.0];
+
+    for i in 1..num_chunks {
+        let target = i as f64 * CHUNK_DURATION_SECONDS;
+        let cut = if search_radius > 0.0 {
+            let path = path.to_string();
+            tokio::task::spawn_blocking(move || sox::find_quietest_point(&path, target, search_radius))
+                .await
+                .ok()
+                .and_then(Result::ok)
+                .unwrap_or(target)
+        } else {
+            target
+        };
+        if cut > *boundaries.last().unwrap() && cut < total_duration {
+            boundaries.push(cut);
+        }
+    }
+
+    boundaries.push(total_duration);
+    boundaries
+}
+
+async fn transcribe_one(
+    path: &str,
+    total_duration: f64,
+    transcriber: &dyn Transcriber,
+    context: ChunkContext,
+    max_attempts: u32,
+    tx: &mpsc::Sender<AppEvent>,
+) -> Result<Vec<Clip>, String> {
+    let chunk_path = env::temp_dir().join(format!("avim_chunk_{}_{}.wav", std::process::id(), context.index));
+
+    let mut trim_cmd = Command::new("sox");
+    trim_cmd.arg(path).arg(&chunk_path).arg("trim").arg(context.start_time.to_string());
+
+    if context.start_time + context.duration < total_duration {
+        trim_cmd.arg(context.duration.to_string());
+    }
+
+    let trimmed = trim_cmd.status().await.map(|s| s.success()).unwrap_or(false);
+    if !trimmed {
+        return Err(format!("Failed to create chunk {}", context.index));
+    }
+
+    let chunk_data = fs::read(&chunk_path).map_err(|e| format!("Failed to read chunk {}: {}", context.index, e))?;
+    fs::remove_file(&chunk_path).ok();
+
+    let mut chunk_clips = transcribe_with_retry(transcriber, &chunk_data, context, max_attempts, tx).await?;
+    for clip in &mut chunk_clips {
+        clip.offset_times(context.start_time);
+    }
+    Ok(chunk_clips)
+}
+
+/// Calls the backend until it succeeds, returns a fatal error, or
+/// `max_attempts` is used up. Waits follow the server's `Retry-After` when
+/// given, capped at `RETRY_MAX_DELAY_SECONDS`, otherwise an exponential
+/// backoff with jitter.
+async fn transcribe_with_retry(
+    transcriber: &dyn Transcriber,
+    chunk_data: &[u8],
+    context: ChunkContext,
+    max_attempts: u32,
+    tx: &mpsc::Sender<AppEvent>,
+) -> Result<Vec<Clip>, String> {
+    let max_attempts = max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match transcriber.transcribe(chunk_data, context).await {
+            Ok(clips) => return Ok(clips),
+            Err(TranscribeError::Retryable { message, retry_after }) if attempt < max_attempts => {
+                let delay = retry_delay(retry_after, attempt);
+                tx.send(AppEvent::StatusUpdate(format!(
+                    "Chunk {} failed ({}). Retrying in {:.1}s (attempt {}/{})...",
+                    context.index + 1, message, delay.as_secs_f64(), attempt + 1, max_attempts
+                ))).await.ok();
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e @ TranscribeError::Retryable { .. }) => {
+                return Err(format!("Chunk {} failed after {} attempts: {}", context.index + 1, attempt, e));
+            }
+            Err(e @ TranscribeError::Fatal(_)) => {
+                return Err(format!("Chunk {} failed: {}", context.index + 1, e));
+            }
+        }
+    }
+}
+
+/// How long to wait before the next attempt: the server's hint capped at
+/// `RETRY_MAX_DELAY_SECONDS`, or the exponential backoff when there is none.
+fn retry_delay(retry_after: Option<Duration>, attempt: u32) -> Duration {
+    retry_after
+        .map(|delay| delay.min(Duration::from_secs_f64(RETRY_MAX_DELAY_SECONDS)))
+        .unwrap_or_else(|| backoff_delay(attempt))
+}
+
+/// Doubles the delay on every attempt up to a cap, then adds up to 50%
+/// jitter so parallel chunks don't retry in lockstep.
+fn backoff_delay(attempt: u32) -> Duration {
+    let base = (RETRY_BASE_DELAY_SECONDS * 2f64.powi(attempt as i32 - 1)).min(RETRY_MAX_DELAY_SECONDS);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+    let jitter = (nanos % 1000) as f64 / 1000.0 * 0.5;
+    Duration::from_secs_f64(base * (1.0 + jitter))
+}
+
+/// Shared chunk counters, reported as e.g. "7/24 done, 3 in flight".
+struct Progress {
+    total: usize,
+    backend: String,
+    done: AtomicUsize,
+    cached: AtomicUsize,
+    in_flight: AtomicUsize,
+}
+
+impl Progress {
+    async fn start(&self, tx: &mpsc::Sender<AppEvent>) {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        self.report(tx).await;
+    }
+
+    async fn finish(&self, tx: &mpsc::Sender<AppEvent>, succeeded: bool) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        if succeeded {
+            self.done.fetch_add(1, Ordering::SeqCst);
+            self.report(tx).await;
+        }
+    }
+
+    async fn cached(&self, tx: &mpsc::Sender<AppEvent>) {
+        self.done.fetch_add(1, Ordering::SeqCst);
+        self.cached.fetch_add(1, Ordering::SeqCst);
+        self.report(tx).await;
+    }
+
+    async fn report(&self, tx: &mpsc::Sender<AppEvent>) {
+        let mut message = format!(
+            "Transcribing with {}: {}/{} done, {} in flight...",
+            self.backend,
+            self.done.load(Ordering::SeqCst),
+            self.total,
+            self.in_flight.load(Ordering::SeqCst)
+        );
+        let cached = self.cached.load(Ordering::SeqCst);
+        if cached > 0 {
+            message.push_str(&format!(" ({} resumed from cache)", cached));
+        }
+        tx.send(AppEvent::StatusUpdate(message)).await.ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_plus_jitter() {
+        for (attempt, base) in [(1, 1.0), (2, 2.0), (4, 8.0), (7, 60.0), (40, 60.0)] {
+            let delay = backoff_delay(attempt).as_secs_f64();
+            assert!(delay >= base && delay <= base * 1.5, "attempt {}: {}", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn retry_after_is_honored_up_to_the_cap() {
+        assert_eq!(retry_delay(Some(Duration::from_secs(5)), 1), Duration::from_secs(5));
+        assert_eq!(retry_delay(Some(Duration::from_secs(3600)), 1), Duration::from_secs(60));
+        assert!(retry_delay(None, 1) <= Duration::from_secs_f64(1.5));
+    }
+}
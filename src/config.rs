@@ -0,0 +1,58 @@
+use std::{error::Error, fs, path::PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// User settings read from `~/.config/avim/config.json`. Every field is
+/// optional in the file; anything missing falls back to the defaults below.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub backend: String,
+    pub gemini_model: String,
+    pub whisper_binary: String,
+    pub whisper_model: String,
+    pub whisper_diarize: bool,
+    pub whisper_server_url: String,
+    /// Maximum number of chunks transcribed at the same time.
+    pub concurrency: usize,
+    /// How many times a chunk is sent before a retryable error gives up.
+    pub max_attempts: u32,
+    /// Seconds each chunk extends into the next one for seam stitching.
+    pub chunk_overlap_seconds: f64,
+    /// How far from each 5-minute mark to look for silence to cut at.
+    pub silence_search_seconds: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            backend: "gemini".to_string(),
+            gemini_model: "gemini-1.5-flash-latest".to_string(),
+            whisper_binary: "whisper-cli".to_string(),
+            whisper_model: "models/ggml-base.en.bin".to_string(),
+            whisper_diarize: false,
+            whisper_server_url: "http://127.0.0.1:8080/inference".to_string(),
+            concurrency: 4,
+            max_attempts: 5,
+            chunk_overlap_seconds: 15.0,
+            silence_search_seconds: 10.0,
+        }
+    }
+}
+
+fn get_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("avim").join("config.json"))
+}
+
+impl Config {
+    pub fn load() -> Result<Config, Box<dyn Error + Send + Sync>> {
+        match get_config_path() {
+            Some(path) if path.exists() => {
+                let file_contents = fs::read_to_string(&path)?;
+                let config = serde_json::from_str(&file_contents)
+                    .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+                Ok(config)
+            }
+            _ => Ok(Config::default()),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::ops::Range;
+use crate::{
+    app::{App, Clip, Cut, Mode},
+    vim::RepeatableChange,
+};
+
+/// `x` / `v`: puts a word cursor on the current clip so words can be cut
+/// from its transcript and audio. With `select`, a word selection starts at
+/// the cursor, like Vim's characterwise Visual mode.
+pub fn enter_cut_mode(app: &mut App, select: bool) {
+    let Some(clip) = app.clips.get(app.current_clip_index) else { return };
+    if !clip.has_word_timings() {
+        app.status_message = "This clip has no word timings, so its words cannot be cut.".to_string();
+        return;
+    }
+    app.mode = Mode::Cut;
+    app.adjust_word_index = 0;
+    app.word_anchor = select.then_some(0);
+    app.status_message = "CUT MODE: 'w'/'b' to move, 'v' to select, 'x' or 'dw' to cut, 'Esc' when done.".to_string();
+}
+
+pub fn exit_cut_mode(app: &mut App) {
+    app.word_anchor = None;
+    app.mode = Mode::Normal;
+}
+
+/// Moves the word cursor `count` words forward or backward, stopping at the
+/// ends of the clip.
+pub fn move_word_cursor(app: &mut App, forward: bool, count: usize) {
+    let word_count = app.clips.get(app.current_clip_index).map_or(0, |c| c.words.len());
+    app.adjust_word_index = if forward {
+        app.adjust_word_index.saturating_add(count).min(word_count.saturating_sub(1))
+    } else {
+        app.adjust_word_index.saturating_sub(count)
+    };
+}
+
+/// `v` in Cut mode: starts or drops a word selection at the cursor.
+pub fn toggle_word_selection(app: &mut App) {
+    app.word_anchor = match app.word_anchor {
+        Some(_) => None,
+        None => Some(app.adjust_word_index),
+    };
+}
+
+/// The selected words, or `count` words from the cursor when nothing is
+/// selected.
+fn selected_words(app: &App, count: usize) -> Range<usize> {
+    let cursor = app.adjust_word_index;
+    match app.word_anchor {
+        Some(anchor) => anchor.min(cursor)..anchor.max(cursor) + 1,
+        None => cursor..cursor.saturating_add(count.max(1)),
+    }
+}
+
+/// `x`, `d` on a selection, or `{count}dw` in Cut mode.
+pub fn cut_selection(app: &mut App, count: usize) {
+    let words = selected_words(app, count);
+    cut_words(app, words.start, words.len());
+}
+
+/// `.` in Cut mode: repeats the last cut at the word cursor. Other changes
+/// are repeated from Normal mode.
+pub fn repeat_cut(app: &mut App, count: Option<usize>) {
+    match app.last_change {
+        Some(RepeatableChange::CutWords { count: original }) => {
+            app.word_anchor = None;
+            cut_selection(app, count.unwrap_or(original));
+        }
+        _ => app.status_message = "Only cuts can be repeated in Cut mode.".to_string(),
+    }
+}
+
+/// Removes `count` words starting at word `first` from the current clip's
+/// transcript and adds their audio to the clip's cuts. Used by Cut mode,
+/// and by `dw` and `.` in Normal mode, which cut from the first word.
+pub fn cut_words(app: &mut App, first: usize, count: usize) {
+    let index = app.current_clip_index;
+    let Some(clip) = app.clips.get(index) else { return };
+    if !clip.has_word_timings() {
+        app.status_message = "This clip has no word timings, so its words cannot be cut.".to_string();
+        return;
+    }
+    let word_count = clip.words.len();
+    let count = count.min(word_count.saturating_sub(first));
+    if count == 0 {
+        return;
+    }
+    if count == word_count {
+        app.status_message = "Cutting every word would leave an empty clip; use dd to delete it.".to_string();
+        return;
+    }
+
+    let cut = cut_span(clip, first, count);
+    app.update_clip(index, None, |clip| {
+        let mut words: Vec<&str> = clip.transcript.split_whitespace().collect();
+        words.drain(first..first + count);
+        clip.transcript = words.join(" ");
+        clip.words.drain(first..first + count);
+        if cut.end > cut.start {
+            clip.add_cut(cut);
+        }
+        clip.is_manually_adjusted = true;
+    });
+    app.last_change = Some(RepeatableChange::CutWords { count });
+    app.word_anchor = None;
+    app.adjust_word_index = first.min(word_count - count - 1);
+    app.status_message = format!(
+        "Cut {} word{} ({:.2}s of audio).",
+        count,
+        if count == 1 { "" } else { "s" },
+        (cut.end - cut.start).max(0.0),
+    );
+}
+
+/// The audio removed with words `first..first + count`: from the start of
+/// the first cut word to the start of the next kept word, so the pause
+/// after the cut words goes with them. Cuts at either end of the clip reach
+/// the clip's boundary.
+fn cut_span(clip: &Clip, first: usize, count: usize) -> Cut {
+    let start = if first == 0 { clip.start_time } else { clip.words[first].start };
+    let end = clip.words.get(first + count).map_or(clip.end_time, |word| word.start);
+    Cut {
+        start: start.clamp(clip.start_time, clip.end_time),
+        end: end.clamp(clip.start_time, clip.end_time),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_with(clip: Clip) -> App {
+        let mut app = App::new("audio.wav".to_string(), None, false);
+        app.clips = vec![clip];
+        app
+    }
+
+    #[test]
+    fn cutting_middle_words_skips_up_to_the_next_word() {
+        let mut app = app_with(Clip::for_test("a b c d e", 0.0, 5.0));
+        cut_words(&mut app, 1, 2);
+        let clip = &app.clips[0];
+        assert_eq!(clip.transcript, "a d e");
+        assert_eq!(clip.words.len(), 3);
+        assert_eq!(clip.cuts, vec![Cut { start: 1.0, end: 3.0 }]);
+        assert_eq!(clip.audible_segments(), vec![(0.0, 1.0), (3.0, 5.0)]);
+    }
+
+    #[test]
+    fn cuts_at_the_ends_reach_the_clip_boundaries() {
+        let mut clip = Clip::for_test("a b c d", 1.0, 5.0);
+        clip.start_time = 0.5;
+        clip.end_time = 5.5;
+        let mut app = app_with(clip);
+        cut_words(&mut app, 3, 1);
+        cut_words(&mut app, 0, 1);
+        let clip = &app.clips[0];
+        assert_eq!(clip.transcript, "b c");
+        assert_eq!(clip.audible_segments(), vec![(2.0, 4.0)]);
+    }
+
+    #[test]
+    fn cutting_every_word_is_refused() {
+        let mut app = app_with(Clip::for_test("a b", 0.0, 2.0));
+        cut_words(&mut app, 0, 5);
+        assert_eq!(app.clips[0].transcript, "a b");
+        assert!(app.clips[0].cuts.is_empty());
+    }
+
+    #[test]
+    fn cuts_undo_with_their_words() {
+        let mut app = app_with(Clip::for_test("a b c", 0.0, 3.0));
+        cut_words(&mut app, 1, 1);
+        crate::vim::undo(&mut app);
+        assert_eq!(app.clips[0], Clip::for_test("a b c", 0.0, 3.0));
+    }
+
+    #[test]
+    fn selection_covers_anchor_to_cursor() {
+        let mut app = app_with(Clip::for_test("a b c d e", 0.0, 5.0));
+        app.adjust_word_index = 3;
+        app.word_anchor = Some(1);
+        cut_selection(&mut app, 1);
+        assert_eq!(app.clips[0].transcript, "a e");
+        assert_eq!(app.word_anchor, None);
+        assert_eq!(app.last_change, Some(RepeatableChange::CutWords { count: 3 }));
+    }
+}
@@ -0,0 +1,338 @@
+use crate::app::{Clip, Word};
+
+/// Which part of a clip an Insert mode session edits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InsertField {
+    Transcript,
+    Comment,
+}
+
+impl InsertField {
+    pub fn label(self) -> &'static str {
+        match self {
+            InsertField::Transcript => "transcript",
+            InsertField::Comment => "comment",
+        }
+    }
+
+    pub fn get(self, clip: &Clip) -> &str {
+        match self {
+            InsertField::Transcript => &clip.transcript,
+            InsertField::Comment => &clip.comment,
+        }
+    }
+}
+
+/// A single line of text with a cursor, counted in characters.
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// Starts with the cursor at the end of `text`.
+    pub fn new(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let cursor = chars.len();
+        LineEditor { chars, cursor }
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    /// Inserts `text` at the cursor, with line breaks turned into spaces.
+    pub fn insert_str(&mut self, text: &str) {
+        for c in text.chars() {
+            self.insert(if c == '\n' || c == '\r' || c == '\t' { ' ' } else { c });
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// `Ctrl+w`: deletes the word before the cursor and any spaces after it.
+    pub fn delete_word_before(&mut self) {
+        let start = self.word_start_before();
+        self.chars.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.chars.len();
+    }
+
+    /// Moves to the start of the current or previous word.
+    pub fn word_left(&mut self) {
+        self.cursor = self.word_start_before();
+    }
+
+    /// Moves to the start of the next word.
+    pub fn word_right(&mut self) {
+        let len = self.chars.len();
+        while self.cursor < len && !self.chars[self.cursor].is_whitespace() {
+            self.cursor += 1;
+        }
+        while self.cursor < len && self.chars[self.cursor].is_whitespace() {
+            self.cursor += 1;
+        }
+    }
+
+    fn word_start_before(&self) -> usize {
+        let mut index = self.cursor;
+        while index > 0 && self.chars[index - 1].is_whitespace() {
+            index -= 1;
+        }
+        while index > 0 && !self.chars[index - 1].is_whitespace() {
+            index -= 1;
+        }
+        index
+    }
+}
+
+/// An Insert mode session on one clip. The clip is updated on every
+/// keystroke so the transcript panel and swap file stay current; the
+/// edits undo as one step.
+#[derive(Debug)]
+pub struct InsertSession {
+    pub clip_index: usize,
+    pub field: InsertField,
+    pub editor: LineEditor,
+    /// The transcript and word timings as they were when the session
+    /// started, which edited words are realigned against.
+    original_transcript: String,
+    original_words: Option<Vec<Word>>,
+}
+
+impl InsertSession {
+    pub fn new(clip: &Clip, clip_index: usize, field: InsertField) -> Self {
+        InsertSession {
+            clip_index,
+            field,
+            editor: LineEditor::new(field.get(clip)),
+            original_transcript: clip.transcript.clone(),
+            original_words: clip.has_word_timings().then(|| clip.words.clone()),
+        }
+    }
+
+    /// Switches between the transcript and the comment, keeping the
+    /// session (and its undo step) open.
+    pub fn switch_field(&mut self, clip: &Clip) {
+        self.field = match self.field {
+            InsertField::Transcript => InsertField::Comment,
+            InsertField::Comment => InsertField::Transcript,
+        };
+        self.editor = LineEditor::new(self.field.get(clip));
+    }
+
+    /// Writes the editor's text into `clip`.
+    pub fn apply(&self, clip: &mut Clip) {
+        let text = self.editor.text();
+        match self.field {
+            InsertField::Comment => clip.comment = text,
+            InsertField::Transcript => {
+                clip.words = match &self.original_words {
+                    Some(words) => realign_words(&self.original_transcript, words, &text, (clip.start_time, clip.end_time)),
+                    None => Vec::new(),
+                };
+                clip.transcript = text;
+            }
+        }
+        clip.is_manually_adjusted = true;
+    }
+}
+
+/// Carries word timings over to an edited transcript. Words shared at the
+/// start and end keep their timings. If the edited middle has as many words
+/// as before (typos fixed) they keep theirs too; otherwise the middle words
+/// are spread evenly over the time the replaced words took. Words added
+/// without replacing any get the gap up to their neighbours, or up to the
+/// clip's `bounds` at either end; with no gap there, they share the
+/// neighbouring word's time.
+pub fn realign_words(old_text: &str, old_words: &[Word], new_text: &str, bounds: (f64, f64)) -> Vec<Word> {
+    let old: Vec<&str> = old_text.split_whitespace().collect();
+    let new: Vec<&str> = new_text.split_whitespace().collect();
+    if old.len() != old_words.len() || old_words.is_empty() {
+        return Vec::new();
+    }
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    // The replaced words are old[start..old_end], their replacements
+    // new[start..new_end].
+    let mut start = prefix;
+    let mut old_end = old.len() - suffix;
+    let mut new_end = new.len() - suffix;
+
+    let middle: Vec<Word> = if old_end - start == new_end - start {
+        old_words[start..old_end].iter().zip(&new[start..new_end]).map(|(word, text)| Word {
+            text: text.to_string(),
+            confidence: if word.text == *text { word.confidence } else { None },
+            ..word.clone()
+        }).collect()
+    } else if new_end == start {
+        Vec::new()
+    } else {
+        let span = |start: usize, old_end: usize| {
+            let from = if old_end > start {
+                old_words[start].start
+            } else if start == 0 {
+                bounds.0
+            } else {
+                old_words[start - 1].end
+            };
+            let to = if old_end > start {
+                old_words[old_end - 1].end
+            } else if old_end == old.len() {
+                bounds.1
+            } else {
+                old_words[old_end].start
+            };
+            (from, to)
+        };
+        let (mut from, mut to) = span(start, old_end);
+        if to <= from && old_end == start {
+            if start > 0 {
+                start -= 1;
+            } else {
+                old_end += 1;
+                new_end += 1;
+            }
+            (from, to) = span(start, old_end);
+        }
+        let replacements = &new[start..new_end];
+        let step = (to - from).max(0.0) / replacements.len() as f64;
+        replacements.iter().enumerate().map(|(i, text)| Word {
+            text: text.to_string(),
+            start: from + step * i as f64,
+            end: from + step * (i + 1) as f64,
+            confidence: None,
+        }).collect()
+    };
+
+    let mut words = old_words[..start].to_vec();
+    words.extend(middle);
+    words.extend_from_slice(&old_words[old_end..]);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Word timings for `text`, one second per word from `start`.
+    fn words(text: &str, start: f64) -> Vec<Word> {
+        Clip::for_test(text, start, start + text.split_whitespace().count() as f64).words
+    }
+
+    fn timings(words: &[Word]) -> Vec<(&str, f64, f64)> {
+        words.iter().map(|w| (w.text.as_str(), w.start, w.end)).collect()
+    }
+
+    #[test]
+    fn fixed_typos_keep_their_timings() {
+        let old = words("teh cat sat", 0.0);
+        let new = realign_words("teh cat sat", &old, "the cat sat", (0.0, 3.0));
+        assert_eq!(timings(&new), vec![("the", 0.0, 1.0), ("cat", 1.0, 2.0), ("sat", 2.0, 3.0)]);
+    }
+
+    #[test]
+    fn replaced_words_share_the_old_span() {
+        let old = words("a b c d", 0.0);
+        let new = realign_words("a b c d", &old, "a w x y z d", (0.0, 4.0));
+        assert_eq!(timings(&new), vec![
+            ("a", 0.0, 1.0),
+            ("w", 1.0, 1.5),
+            ("x", 1.5, 2.0),
+            ("y", 2.0, 2.5),
+            ("z", 2.5, 3.0),
+            ("d", 3.0, 4.0),
+        ]);
+    }
+
+    #[test]
+    fn removed_words_drop_their_timings() {
+        let old = words("a b c", 0.0);
+        let new = realign_words("a b c", &old, "a c", (0.0, 3.0));
+        assert_eq!(timings(&new), vec![("a", 0.0, 1.0), ("c", 2.0, 3.0)]);
+    }
+
+    #[test]
+    fn appended_words_reach_the_clip_end() {
+        let old = words("a b", 1.0);
+        let new = realign_words("a b", &old, "a b c d", (0.5, 5.0));
+        assert_eq!(timings(&new), vec![("a", 1.0, 2.0), ("b", 2.0, 3.0), ("c", 3.0, 4.0), ("d", 4.0, 5.0)]);
+    }
+
+    #[test]
+    fn prepended_words_reach_the_clip_start() {
+        let old = words("b c", 2.0);
+        let new = realign_words("b c", &old, "a b c", (1.0, 4.0));
+        assert_eq!(timings(&new), vec![("a", 1.0, 2.0), ("b", 2.0, 3.0), ("c", 3.0, 4.0)]);
+    }
+
+    #[test]
+    fn words_added_without_a_gap_share_the_neighbouring_word() {
+        let old = words("a b", 0.0);
+        let new = realign_words("a b", &old, "a b c", (0.0, 2.0));
+        assert_eq!(timings(&new), vec![("a", 0.0, 1.0), ("b", 1.0, 1.5), ("c", 1.5, 2.0)]);
+
+        let new = realign_words("a b", &old, "z a b", (0.0, 2.0));
+        assert_eq!(timings(&new), vec![("z", 0.0, 0.5), ("a", 0.5, 1.0), ("b", 1.0, 2.0)]);
+    }
+
+    #[test]
+    fn mismatched_timings_are_dropped() {
+        assert!(realign_words("a b c", &words("a b", 0.0), "a b c d", (0.0, 4.0)).is_empty());
+    }
+
+    #[test]
+    fn line_editor_word_motions_and_deletes() {
+        let mut editor = LineEditor::new("hello brave new world");
+        editor.word_left();
+        assert_eq!(editor.cursor(), 16);
+        editor.word_left();
+        editor.delete_word_before();
+        assert_eq!((editor.text().as_str(), editor.cursor()), ("hello new world", 6));
+        editor.home();
+        editor.word_right();
+        assert_eq!(editor.cursor(), 6);
+        editor.insert_str("old\n");
+        assert_eq!(editor.text(), "hello old new world");
+    }
+}
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
-use std::{env, error::Error};
+use std::env;
 use base64::{Engine as _, engine::general_purpose};
-use crate::app::Clip;
+use sha2::{Digest, Sha256};
+use crate::{app::Clip, transcriber::{retry_after_header, ChunkContext, TranscribeError, TranscribeFuture, Transcriber}};
 
 #[derive(Serialize)] pub struct GeminiRequest { pub contents: Vec<Content> }
 #[derive(Serialize)] pub struct Content { pub parts: Vec<Part> }
@@ -12,16 +13,42 @@ use crate::app::Clip;
 #[derive(Deserialize, Debug)] pub struct ResponseContent { pub parts: Vec<ResponsePart> }
 #[derive(Deserialize, Debug)] pub struct ResponsePart { pub text: String }
 
-pub async fn transcribe_chunk(audio_data: &[u8]) -> Result<Vec<Clip>, Box<dyn Error + Send + Sync>> {
-    let api_key = env::var("GEMINI_API_KEY").map_err(|_| "GEMINI_API_KEY not set in environment")?;
+const PROMPT: &str = "Transcribe this audio. Identify speakers. Segment the audio into clips based on pauses or speaker changes. Provide the output as a valid JSON array of objects, where each object has 'id', 'speaker', 'transcript', 'start_time', 'end_time' and 'words'. 'words' is an array with one object per whitespace-separated word of the transcript, in order, each with 'text', 'start' and 'end' in seconds. The JSON should be the only thing in your response.";
+
+pub struct GeminiTranscriber {
+    model: String,
+}
+
+impl GeminiTranscriber {
+    pub fn new(model: String) -> Self {
+        GeminiTranscriber { model }
+    }
+}
+
+impl Transcriber for GeminiTranscriber {
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    fn fingerprint(&self) -> String {
+        let prompt_hash = hex::encode(Sha256::digest(PROMPT.as_bytes()));
+        format!("gemini/{}/{}", self.model, &prompt_hash[..12])
+    }
+
+    fn transcribe<'a>(&'a self, audio_data: &'a [u8], _context: ChunkContext) -> TranscribeFuture<'a> {
+        Box::pin(transcribe_chunk(&self.model, audio_data))
+    }
+}
+
+pub async fn transcribe_chunk(model: &str, audio_data: &[u8]) -> Result<Vec<Clip>, TranscribeError> {
+    let api_key = env::var("GEMINI_API_KEY").map_err(|_| "GEMINI_API_KEY not set in environment".to_string())?;
     let audio_base64 = general_purpose::STANDARD.encode(audio_data);
 
-    let prompt = "Transcribe this audio. Identify speakers. Segment the audio into clips based on pauses or speaker changes. Provide the output as a valid JSON array of objects, where each object has 'id', 'speaker', 'transcript', 'start_time', and 'end_time'. The JSON should be the only thing in your response.";
 
     let request_body = GeminiRequest {
         contents: vec![Content {
             parts: vec![
-                Part::Text { text: prompt.to_string() },
+                Part::Text { text: PROMPT.to_string() },
                 Part::InlineData {
                     inline_data: InlineData {
                         mime_type: "audio/wav".to_string(),
@@ -34,24 +61,29 @@ pub async fn transcribe_chunk(audio_data: &[u8]) -> Result<Vec<Clip>, Box<dyn Er
 
     let client = reqwest::Client::new();
     let res = client
-        .post(format!("https://generativelanguage.googleapis.com/v1beta/models/gemini-1.5-flash-latest:generateContent?key={}", api_key))
+        .post(format!("https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}", model, api_key))
         .json(&request_body)
         .send()
         .await?;
 
     if !res.status().is_success() {
-        return Err(format!("API Error: {}", res.text().await?).into());
+        let status = res.status();
+        let retry_after = retry_after_header(res.headers());
+        let message = format!("API Error ({}): {}", status, res.text().await.unwrap_or_default());
+        return Err(TranscribeError::from_status(status, retry_after, message));
     }
 
     let response_body: GeminiResponse = res.json().await?;
-    let text_part = response_body.candidates.get(0)
+    let text_part = response_body.candidates.first()
         .and_then(|c| c.content.as_ref())
-        .and_then(|c| c.parts.get(0))
+        .and_then(|c| c.parts.first())
         .map(|p| p.text.trim().trim_start_matches("```json").trim_end_matches("```").trim())
         .unwrap_or_default();
 
+    // The model occasionally answers with malformed JSON; asking again
+    // usually fixes it, so this is worth a retry.
     let clips: Vec<Clip> = serde_json::from_str(text_part)
-        .map_err(|e| format!("Failed to parse JSON from Gemini response: {}. Response text: '{}'", e, text_part))?;
+        .map_err(|e| TranscribeError::retryable(format!("Failed to parse JSON from Gemini response: {}. Response text: '{}'", e, text_part)))?;
 
     Ok(clips)
 }
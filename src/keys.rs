@@ -0,0 +1,239 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::registers::Registers;
+
+/// Counts are capped here so that a stray `999999999p` or `999999999]`
+/// can't allocate or loop without bound.
+pub const MAX_COUNT: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Delete,
+    Yank,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motion {
+    Down,
+    Up,
+    /// `gg`, or `{count}gg` to go to that clip.
+    First,
+    /// `G`, or `{count}G` to go to that clip.
+    Last,
+    /// The operator repeated (`dd`, `yy`): the current clip plus count - 1 below.
+    Line,
+    /// `}` / `{` after an operator: to the end or start of the run of clips
+    /// by the same speaker. Without an operator these keys nudge end times.
+    SpeakerRunForward,
+    SpeakerRunBackward,
+    /// `w` / `b`: words within the current clip. They move the Cut mode word
+    /// cursor; `dw` cuts words.
+    WordForward,
+    WordBackward,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Move(Motion),
+    Operate(Operator, Motion),
+    /// `g-` / `g+`
+    UndoChronologically { forward: bool },
+    /// Any other key, left to the Normal mode handler.
+    Key(KeyEvent),
+}
+
+/// A complete Normal mode command. `count` is `None` when no count was
+/// typed, so that `G` and `5G` can behave differently. `register` is the
+/// name given with a `"x` prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Command {
+    pub action: Action,
+    pub count: Option<usize>,
+    pub register: Option<char>,
+}
+
+impl Command {
+    pub fn count_or_one(&self) -> usize {
+        self.count.unwrap_or(1).max(1)
+    }
+}
+
+/// Collects Normal mode keys until they form a command, following Vim's
+/// `["x] [count] operator [count] motion` grammar: `5j`, `3dd`, `d2j`,
+/// `yG`, `d}`, `dw`, `gg`, `10]`, `"a3yy`. An invalid sequence is dropped.
+#[derive(Debug, Default)]
+pub struct KeyParser {
+    count: Option<usize>,
+    operator: Option<(Operator, Option<usize>)>,
+    pending_g: bool,
+    register: Option<char>,
+    pending_register: bool,
+}
+
+impl KeyParser {
+    pub fn feed(&mut self, key: KeyEvent) -> Option<Command> {
+        let pending = self.operator.is_some() || self.pending_g || self.count.is_some()
+            || self.register.is_some() || self.pending_register;
+        let c = match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => c,
+            KeyCode::Esc if pending => {
+                self.reset();
+                return None;
+            }
+            _ if self.operator.is_some() || self.pending_g || self.pending_register => {
+                self.reset();
+                return None;
+            }
+            _ => return self.finish(Action::Key(key)),
+        };
+
+        if self.pending_register {
+            self.pending_register = false;
+            if Registers::is_valid_name(c) {
+                self.register = Some(c);
+            } else {
+                self.reset();
+            }
+            return None;
+        }
+
+        if self.pending_g {
+            self.pending_g = false;
+            return match c {
+                'g' => self.motion(Motion::First),
+                '-' | '+' if self.operator.is_none() => self.finish(Action::UndoChronologically { forward: c == '+' }),
+                _ => {
+                    self.reset();
+                    None
+                }
+            };
+        }
+
+        if let Some(digit) = c.to_digit(10) {
+            if digit != 0 || self.count.is_some() {
+                self.count = Some(self.count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize).min(MAX_COUNT));
+                return None;
+            }
+        }
+
+        match (c, self.operator) {
+            ('g', _) => {
+                self.pending_g = true;
+                None
+            }
+            ('"', None) => {
+                self.pending_register = true;
+                None
+            }
+            ('j', _) => self.motion(Motion::Down),
+            ('k', _) => self.motion(Motion::Up),
+            ('G', _) => self.motion(Motion::Last),
+            ('w', _) => self.motion(Motion::WordForward),
+            ('b', _) => self.motion(Motion::WordBackward),
+            ('}', Some(_)) => self.motion(Motion::SpeakerRunForward),
+            ('{', Some(_)) => self.motion(Motion::SpeakerRunBackward),
+            ('d', None) | ('y', None) => {
+                let operator = if c == 'd' { Operator::Delete } else { Operator::Yank };
+                self.operator = Some((operator, self.count.take()));
+                None
+            }
+            ('d', Some((Operator::Delete, _))) | ('y', Some((Operator::Yank, _))) => self.motion(Motion::Line),
+            (_, Some(_)) => {
+                self.reset();
+                None
+            }
+            (_, None) => self.finish(Action::Key(key)),
+        }
+    }
+
+    fn motion(&mut self, motion: Motion) -> Option<Command> {
+        let action = match self.operator {
+            Some((operator, _)) => Action::Operate(operator, motion),
+            None => Action::Move(motion),
+        };
+        self.finish(action)
+    }
+
+    /// Counts typed before the operator and before the motion multiply, as
+    /// in Vim: `2d3j` deletes seven clips.
+    fn finish(&mut self, action: Action) -> Option<Command> {
+        let count = match (self.operator.and_then(|(_, count)| count), self.count) {
+            (Some(a), Some(b)) => Some(a.saturating_mul(b).min(MAX_COUNT)),
+            (a, b) => a.or(b),
+        };
+        let register = self.register;
+        self.reset();
+        Some(Command { action, count, register })
+    }
+
+    fn reset(&mut self) {
+        *self = KeyParser::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `keys` one character at a time and returns what the last one
+    /// produced.
+    fn feed(parser: &mut KeyParser, keys: &str) -> Option<Command> {
+        keys.chars().map(|c| parser.feed(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))).last().flatten()
+    }
+
+    fn parse(keys: &str) -> Option<(Action, Option<usize>, Option<char>)> {
+        feed(&mut KeyParser::default(), keys).map(|c| (c.action, c.count, c.register))
+    }
+
+    #[test]
+    fn motions_take_counts() {
+        assert_eq!(parse("j"), Some((Action::Move(Motion::Down), None, None)));
+        assert_eq!(parse("12k"), Some((Action::Move(Motion::Up), Some(12), None)));
+        assert_eq!(parse("gg"), Some((Action::Move(Motion::First), None, None)));
+        assert_eq!(parse("5G"), Some((Action::Move(Motion::Last), Some(5), None)));
+    }
+
+    #[test]
+    fn operator_counts_multiply() {
+        assert_eq!(parse("2d3j"), Some((Action::Operate(Operator::Delete, Motion::Down), Some(6), None)));
+        assert_eq!(parse("3dd"), Some((Action::Operate(Operator::Delete, Motion::Line), Some(3), None)));
+        assert_eq!(parse("yG"), Some((Action::Operate(Operator::Yank, Motion::Last), None, None)));
+        assert_eq!(parse("dw"), Some((Action::Operate(Operator::Delete, Motion::WordForward), None, None)));
+    }
+
+    #[test]
+    fn braces_are_motions_only_after_an_operator() {
+        assert_eq!(parse("d}"), Some((Action::Operate(Operator::Delete, Motion::SpeakerRunForward), None, None)));
+        assert!(matches!(parse("}"), Some((Action::Key(_), None, None))));
+    }
+
+    #[test]
+    fn register_prefix() {
+        assert_eq!(parse("\"a3yy"), Some((Action::Operate(Operator::Yank, Motion::Line), Some(3), Some('a'))));
+        assert!(matches!(parse("\"Bp"), Some((Action::Key(_), None, Some('B')))));
+        assert_eq!(parse("\"!"), None);
+    }
+
+    #[test]
+    fn g_prefixed_commands() {
+        assert_eq!(parse("g-"), Some((Action::UndoChronologically { forward: false }, None, None)));
+        assert_eq!(parse("3g+"), Some((Action::UndoChronologically { forward: true }, Some(3), None)));
+        assert_eq!(parse("gx"), None);
+    }
+
+    #[test]
+    fn invalid_sequences_are_dropped() {
+        let mut parser = KeyParser::default();
+        assert!(feed(&mut parser, "3dy").is_none());
+        assert_eq!(feed(&mut parser, "j").map(|c| c.count), Some(None));
+
+        assert!(feed(&mut parser, "5d").is_none());
+        assert!(parser.feed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)).is_none());
+        assert_eq!(feed(&mut parser, "j").map(|c| c.count), Some(None));
+    }
+
+    #[test]
+    fn counts_are_capped() {
+        assert_eq!(parse("99999999999999999999j").and_then(|(_, count, _)| count), Some(MAX_COUNT));
+        assert_eq!(parse("9999d9999j").and_then(|(_, count, _)| count), Some(MAX_COUNT));
+    }
+}
@@ -1,8 +1,8 @@
-use std::{env, error::Error, time::Duration, process::Command, fs};
+use std::{env, error::Error, time::Duration};
 use tokio::sync::mpsc;
 use crossterm::{
     cursor::SetCursorStyle,
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyEvent, KeyModifiers},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -15,10 +15,26 @@ mod sox;
 mod cache;
 mod vim;
 mod autofix;
+mod chunking;
+mod stitch;
+mod config;
+mod transcriber;
+mod whisper;
+mod project;
+mod swap;
+mod keys;
+mod editor;
+mod cut;
+mod registers;
+mod undo;
 
 use crate::app::{App, AppEvent, AppState, Mode};
-
-const CHUNK_DURATION_SECONDS: f64 = 300.0;
+use crate::config::Config;
+use crate::project::{MediaStatus, Project};
+use crate::undo::UndoTree;
+use crate::keys::{Action, KeyParser, Motion, Operator};
+use crate::registers::Registers;
+use crate::editor::InsertField;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
@@ -26,20 +42,46 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     let mut use_cache = true;
     let mut debug_mode = false;
 
-    for arg in env::args().skip(1) {
+    let cli_args: Vec<String> = env::args().skip(1).collect();
+    if cli_args.first().map(String::as_str) == Some("cache") {
+        if let Err(e) = cache::run_cli(&cli_args[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Loaded after the cache subcommands, which don't need it, so a broken
+    // config file never stands in the way of cleaning up the cache.
+    let mut config = Config::load()?;
+    let mut args = cli_args.into_iter();
+    while let Some(arg) = args.next() {
         if arg == "--no-cache" { use_cache = false; }
         else if arg == "--debug" { debug_mode = true; }
+        else if arg == "--backend" {
+            match args.next() {
+                Some(name) => config.backend = name,
+                None => {
+                    eprintln!("--backend requires a value, e.g. --backend gemini");
+                    std::process::exit(1);
+                }
+            }
+        }
+        else if let Some(name) = arg.strip_prefix("--backend=") { config.backend = name.to_string(); }
         else if input_path.is_none() { input_path = Some(arg); }
     }
 
     let input_path = match input_path {
         Some(p) => p,
         None => {
-            eprintln!("Usage: avim [--no-cache] [--debug] <audio_file.wav | project_file.avim>");
+            eprintln!("Usage: avim [--no-cache] [--debug] [--backend <name>] <audio_file.wav | project_file.avim>");
+            eprintln!("       avim cache ls | rm <hash prefix | audio file>... | gc [--older-than <age>]");
             std::process::exit(1);
         }
     };
 
+    let transcriber = transcriber::from_config(&config)?;
+
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture, SetCursorStyle::SteadyBlock)?;
@@ -48,94 +90,65 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
 
     let (tx, mut rx) = mpsc::channel(100);
     
-    let (audio_path, project_path) = if input_path.ends_with(".avim") {
-        let (audio_path, clips) = App::load_project(&input_path)?;
-        let duration_output = Command::new("soxi").arg("-D").arg(&audio_path).output().unwrap();
-        let duration = String::from_utf8(duration_output.stdout).unwrap_or_default().trim().parse::<f64>().unwrap_or(0.0);
-        tx.send(AppEvent::TranscriptionSuccess(clips, duration)).await.ok();
-        (audio_path, Some(input_path.clone()))
+    let mut expected_audio_hash = None;
+    let mut undo_tree = UndoTree::default();
+    let (audio_path, project_path, project_created_at) = if input_path.ends_with(".avim") {
+        let project = Project::load(&input_path)?;
+        if let Some(history) = project.undo_history {
+            undo_tree = UndoTree::from_history(history, &project.clips);
+        }
+        let duration = sox::get_duration(&project.audio.path).unwrap_or(project.audio.duration);
+        tx.send(AppEvent::TranscriptionSuccess(project.clips, duration)).await.ok();
+        if let Some(warning) = MediaStatus::check(&project.audio).warning(&project.audio.path) {
+            tx.send(AppEvent::StatusUpdate(warning)).await.ok();
+        }
+        expected_audio_hash = Some(project.audio.hash);
+        (project.audio.path, Some(input_path.clone()), Some(project.created_at))
     } else {
         tokio::spawn({
             let tx = tx.clone();
             let path = input_path.clone();
             async move {
-                if use_cache {
-                    if let Some(clips) = cache::load_from_cache(&path).await {
-                        let duration_output = Command::new("soxi").arg("-D").arg(&path).output().unwrap();
-                        let duration = String::from_utf8(duration_output.stdout).unwrap_or_default().trim().parse::<f64>().unwrap_or(0.0);
-                        tx.send(AppEvent::TranscriptionSuccess(clips, duration)).await.ok();
-                        return;
-                    }
-                }
-                
-                let duration_output = Command::new("soxi").arg("-D").arg(&path).output();
-                let total_duration = match duration_output {
-                    Ok(output) => String::from_utf8(output.stdout).unwrap_or_default().trim().parse::<f64>().unwrap_or(0.0),
-                    Err(_) => {
-                        tx.send(AppEvent::TranscriptionFailure("Failed to execute soxi.".to_string())).await.ok();
+                tx.send(AppEvent::StatusUpdate("Hashing audio file...".to_string())).await.ok();
+                let cache_key = cache::CacheKey::for_file(&path, transcriber.fingerprint()).ok();
+                let total_duration = match sox::get_duration(&path) {
+                    Some(duration) if duration > 0.0 => duration,
+                    _ => {
+                        tx.send(AppEvent::TranscriptionFailure("Could not get audio duration. Is SoX installed?".to_string())).await.ok();
                         return;
                     }
                 };
-                
-                if total_duration == 0.0 {
-                    tx.send(AppEvent::TranscriptionFailure("Could not get audio duration.".to_string())).await.ok();
-                    return;
-                }
-
-                let num_chunks = (total_duration / CHUNK_DURATION_SECONDS).ceil() as i32;
-                let mut all_clips = Vec::new();
 
-                for i in 0..num_chunks {
-                    tx.send(AppEvent::StatusUpdate(format!("Transcribing chunk {} of {}...", i + 1, num_chunks))).await.ok();
-                    let chunk_start_time = i as f64 * CHUNK_DURATION_SECONDS;
-                    let chunk_path = env::temp_dir().join(format!("avim_chunk_{}.wav", i));
-
-                    let mut trim_cmd = Command::new("sox");
-                    trim_cmd.arg(&path).arg(chunk_path.to_str().unwrap()).arg("trim").arg(chunk_start_time.to_string());
-                    
-                    if chunk_start_time + CHUNK_DURATION_SECONDS < total_duration {
-                        trim_cmd.arg(CHUNK_DURATION_SECONDS.to_string());
+                if let (true, Some(key)) = (use_cache, &cache_key) {
+                    if let Some(clips) = cache::load_from_cache(key).await {
+                        tx.send(AppEvent::TranscriptionSuccess(clips, total_duration)).await.ok();
+                        return;
                     }
+                }
 
-                    if !trim_cmd.status().unwrap().success() {
-                        tx.send(AppEvent::TranscriptionFailure(format!("Failed to create chunk {}", i))).await.ok();
+                let sanitized_clips = match chunking::transcribe_file(&path, total_duration, transcriber, &config, cache_key.clone(), use_cache, &tx).await {
+                    Ok(clips) => clips,
+                    Err(e) => {
+                        tx.send(AppEvent::TranscriptionFailure(e)).await.ok();
                         return;
                     }
+                };
 
-                    let chunk_data = fs::read(&chunk_path).unwrap();
-                    fs::remove_file(chunk_path).ok();
-
-                    match gcp::transcribe_chunk(&chunk_data).await {
-                        Ok(mut chunk_clips) => {
-                            for clip in &mut chunk_clips {
-                                clip.start_time += chunk_start_time;
-                                clip.end_time += chunk_start_time;
-                            }
-                            all_clips.extend(chunk_clips);
-                        }
-                        Err(e) => {
-                            tx.send(AppEvent::TranscriptionFailure(e.to_string())).await.ok();
-                            return;
-                        }
+                if let Some(key) = &cache_key {
+                    if cache::save_to_cache(key, &sanitized_clips).await.is_ok() {
+                        cache::clear_chunk_cache(key).await.ok();
                     }
                 }
-
-                let sanitized_clips: Vec<_> = all_clips.into_iter()
-                    .filter(|clip| clip.start_time < total_duration)
-                    .map(|mut clip| {
-                        if clip.end_time > total_duration { clip.end_time = total_duration; }
-                        clip
-                    })
-                    .collect();
-
-                cache::save_to_cache(&path, &sanitized_clips).await.ok();
                 tx.send(AppEvent::TranscriptionSuccess(sanitized_clips, total_duration)).await.ok();
             }
         });
-        (input_path, None)
+        (input_path, None, None)
     };
 
     let mut app = App::new(audio_path, project_path, debug_mode);
+    app.project_created_at = project_created_at;
+    app.expected_audio_hash = expected_audio_hash;
+    app.undo_tree = undo_tree;
 
     tokio::spawn({
         let tx = tx.clone();
@@ -150,16 +163,63 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
         }
     });
 
-    let mut last_key: Option<KeyEvent> = None;
+    tokio::spawn({
+        let tx = tx.clone();
+        async move {
+            let mut interval = tokio::time::interval(swap::SWAP_INTERVAL);
+            loop {
+                interval.tick().await;
+                if tx.send(AppEvent::AutosaveTick).await.is_err() { break; }
+            }
+        }
+    });
+
+    let mut key_parser = KeyParser::default();
+    let mut visual_register = None;
+    let mut awaiting_register = false;
+    let mut last_clip_index = app.current_clip_index;
     loop {
         terminal.draw(|f| ui::ui(f, &mut app))?;
 
         match rx.recv().await {
             Some(AppEvent::Input(key)) => {
+                // Moving to another clip ends a run of merged edits, so coming
+                // back and nudging again is a separate undo step.
+                if app.current_clip_index != last_clip_index {
+                    app.undo_tree.seal();
+                    last_clip_index = app.current_clip_index;
+                }
                 if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL {
                     app.should_quit = true;
                 }
 
+                // Any key closes an open info panel.
+                if app.info_panel.take().is_some() && !app.should_quit {
+                    continue;
+                }
+
+                if let Some(swap_path) = app.pending_recovery.clone() {
+                    if !app.should_quit {
+                        match key.code {
+                            KeyCode::Char('r') => swap::recover(&mut app, &swap_path),
+                            KeyCode::Char('d') => {
+                                swap::delete_swap(&mut app);
+                                app.status_message = "Swap file deleted.".to_string();
+                            }
+                            KeyCode::Esc | KeyCode::Char('i') => match swap::set_aside(&swap_path) {
+                                Ok(kept) => app.status_message = format!("Ignoring swap file; kept it as {}.", kept.display()),
+                                Err(e) => {
+                                    app.status_message = format!("Could not move the swap file aside ({}). Press r to recover or d to delete it.", e);
+                                    continue;
+                                }
+                            },
+                            _ => continue,
+                        }
+                        app.pending_recovery = None;
+                        continue;
+                    }
+                }
+
                 match app.state {
                     AppState::Loading(_) => {
                         if key.code == KeyCode::Char('q') { app.should_quit = true; }
@@ -167,62 +227,88 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
                     AppState::Ready => {
                         match app.mode {
                             Mode::Normal => {
-                                if let Some(last) = last_key {
-                                    if last.code == KeyCode::Char('d') && key.code == KeyCode::Char('d') { vim::delete_clip(&mut app); }
-                                    else if last.code == KeyCode::Char('y') && key.code == KeyCode::Char('y') { vim::yank_clip(&mut app); }
-                                    last_key = None;
-                                } else {
-                                    match key.code {
-                                        KeyCode::Char(':') => app.mode = Mode::Command,
-                                        KeyCode::Char('q') => app.should_quit = true,
-                                        KeyCode::Char('j') => vim::next_clip(&mut app),
-                                        KeyCode::Char('k') => vim::previous_clip(&mut app),
-                                        KeyCode::Char('p') => vim::paste_clip(&mut app),
-                                        KeyCode::Char('u') => vim::undo(&mut app),
-                                        KeyCode::Char('r') if key.modifiers == KeyModifiers::CONTROL => vim::redo(&mut app),
-                                        KeyCode::Char('[') => vim::adjust_start_time(&mut app, false),
-                                        KeyCode::Char(']') => vim::adjust_start_time(&mut app, true),
-                                        KeyCode::Char('{') => vim::adjust_end_time(&mut app, false),
-                                        KeyCode::Char('}') => vim::adjust_end_time(&mut app, true),
-                                        KeyCode::Char('i') => app.mode = Mode::Insert,
-                                        KeyCode::Char('m') => autofix::enter_adjust_mode(&mut app),
-                                        KeyCode::Char('P') => {
-                                            if app.playback_pid.is_some() {
-                                                sox::stop_playback(app.playback_pid.unwrap());
-                                                app.playback_pid = None;
-                                                app.status_message = "Playback stopped.".to_string();
-                                            } else {
-                                                app.status_message = "Playing all from current clip...".to_string();
-                                                match sox::play_playlist(&app, app.current_clip_index) {
-                                                    Ok(child) => app.playback_pid = Some(child.id()),
-                                                    Err(e) => app.status_message = format!("Playback failed: {}", e),
+                                let Some(command) = key_parser.feed(key) else { continue };
+                                let count = command.count_or_one();
+                                match command.action {
+                                    Action::Move(motion) => vim::move_cursor(&mut app, motion, command.count),
+                                    Action::Operate(operator, motion) => vim::operate(&mut app, operator, motion, command.count, command.register),
+                                    Action::UndoChronologically { forward } => {
+                                        vim::undo_chronologically(&mut app, if forward { count as i64 } else { -(count as i64) })
+                                    }
+                                    Action::Key(key) => {
+                                        match key.code {
+                                            KeyCode::Char(':') => app.mode = Mode::Command,
+                                            KeyCode::Char('q') => app.should_quit = true,
+                                            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                                if let Some(pid) = app.playback_pid.take() {
+                                                    sox::stop_playback(pid);
+                                                    app.status_message = "Playback stopped.".to_string();
+                                                } else {
+                                                    app.status_message = "Playing all from current clip...".to_string();
+                                                    match sox::play_playlist(&app, app.current_clip_index..app.clips.len()) {
+                                                        Ok(child) => app.playback_pid = Some(child.id()),
+                                                        Err(e) => app.status_message = format!("Playback failed: {}", e),
+                                                    }
                                                 }
                                             }
-                                        },
-                                        KeyCode::Char(' ') => {
-                                            if let Some(pid) = app.playback_pid {
-                                                sox::stop_playback(pid);
-                                                app.playback_pid = None;
-                                                app.status_message = "Playback stopped.".to_string();
-                                            } else if let Some(clip) = app.clips.get(app.current_clip_index).cloned() {
-                                                let path = app.original_audio_path.clone();
-                                                app.status_message = format!("Playing clip {}...", app.current_clip_index + 1);
-                                                match sox::play_clip_with_sox(&path, clip.start_time, clip.end_time) {
-                                                    Ok(child) => app.playback_pid = Some(child.id()),
-                                                    Err(e) => app.status_message = format!("Playback failed: {}", e),
+                                            KeyCode::Char('p') => vim::paste_clip(&mut app, count, command.register, false),
+                                            KeyCode::Char('P') => vim::paste_clip(&mut app, count, command.register, true),
+                                            KeyCode::Char('.') => vim::repeat_last_change(&mut app, command.count),
+                                            KeyCode::Char('u') => (0..count).for_each(|_| vim::undo(&mut app)),
+                                            KeyCode::Char('r') if key.modifiers == KeyModifiers::CONTROL => (0..count).for_each(|_| vim::redo(&mut app)),
+                                            KeyCode::Char('[') => vim::adjust_start_time(&mut app, false, count),
+                                            KeyCode::Char(']') => vim::adjust_start_time(&mut app, true, count),
+                                            KeyCode::Char('{') => vim::adjust_end_time(&mut app, false, count),
+                                            KeyCode::Char('}') => vim::adjust_end_time(&mut app, true, count),
+                                            KeyCode::Char('i') => vim::enter_insert_mode(&mut app, InsertField::Comment),
+                                            KeyCode::Char('I') => vim::enter_insert_mode(&mut app, InsertField::Transcript),
+                                            KeyCode::Char('m') => autofix::enter_adjust_mode(&mut app),
+                                            KeyCode::Char('s') => autofix::enter_split_mode(&mut app),
+                                            KeyCode::Char('J') => vim::join_clips(&mut app, count),
+                                            KeyCode::Char('V') => vim::enter_visual_mode(&mut app),
+                                            KeyCode::Char('x') => cut::enter_cut_mode(&mut app, false),
+                                            KeyCode::Char('v') => cut::enter_cut_mode(&mut app, true),
+                                            KeyCode::Char(' ') => {
+                                                if let Some(pid) = app.playback_pid {
+                                                    sox::stop_playback(pid);
+                                                    app.playback_pid = None;
+                                                    app.status_message = "Playback stopped.".to_string();
+                                                } else if let Some(clip) = app.clips.get(app.current_clip_index).cloned() {
+                                                    let path = app.original_audio_path.clone();
+                                                    app.status_message = format!("Playing clip {}...", app.current_clip_index + 1);
+                                                    match sox::play_clip_with_sox(&path, &clip.audible_segments()) {
+                                                        Ok(child) => app.playback_pid = Some(child.id()),
+                                                        Err(e) => app.status_message = format!("Playback failed: {}", e),
+                                                    }
                                                 }
                                             }
+                                            _ => {}
                                         }
-                                        KeyCode::Char('d') | KeyCode::Char('y') => last_key = Some(key),
-                                        _ => {}
                                     }
                                 }
                             },
                             Mode::Insert => {
+                                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                                let alt = key.modifiers.contains(KeyModifiers::ALT);
                                 match key.code {
-                                    KeyCode::Esc => app.mode = Mode::Normal,
-                                    KeyCode::Char(c) => vim::append_to_comment(&mut app, c),
-                                    KeyCode::Backspace => vim::pop_from_comment(&mut app),
+                                    KeyCode::Esc => vim::exit_insert_mode(&mut app),
+                                    KeyCode::Tab => vim::switch_insert_field(&mut app),
+                                    KeyCode::Left if ctrl => vim::move_text_cursor(&mut app, |e| e.word_left()),
+                                    KeyCode::Right if ctrl => vim::move_text_cursor(&mut app, |e| e.word_right()),
+                                    KeyCode::Char('b') if alt => vim::move_text_cursor(&mut app, |e| e.word_left()),
+                                    KeyCode::Char('f') if alt => vim::move_text_cursor(&mut app, |e| e.word_right()),
+                                    KeyCode::Char('a') if ctrl => vim::move_text_cursor(&mut app, |e| e.home()),
+                                    KeyCode::Char('e') if ctrl => vim::move_text_cursor(&mut app, |e| e.end()),
+                                    KeyCode::Char('w') if ctrl => vim::edit_text(&mut app, |e| e.delete_word_before()),
+                                    KeyCode::Char('v') if ctrl => vim::paste_from_clipboard(&mut app),
+                                    KeyCode::Char(_) if ctrl || alt => {}
+                                    KeyCode::Char(c) => vim::edit_text(&mut app, |e| e.insert(c)),
+                                    KeyCode::Left => vim::move_text_cursor(&mut app, |e| e.left()),
+                                    KeyCode::Right => vim::move_text_cursor(&mut app, |e| e.right()),
+                                    KeyCode::Home => vim::move_text_cursor(&mut app, |e| e.home()),
+                                    KeyCode::End => vim::move_text_cursor(&mut app, |e| e.end()),
+                                    KeyCode::Backspace => vim::edit_text(&mut app, |e| e.backspace()),
+                                    KeyCode::Delete => vim::edit_text(&mut app, |e| e.delete()),
                                     _ => {}
                                 }
                             }
@@ -231,10 +317,78 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
                                     KeyCode::Enter => app.process_command(),
                                     KeyCode::Char(c) => app.command_input.push(c),
                                     KeyCode::Backspace => { app.command_input.pop(); },
-                                    KeyCode::Esc => { app.mode = Mode::Normal; app.command_input.clear(); }
+                                    KeyCode::Esc => {
+                                        app.mode = Mode::Normal;
+                                        app.command_input.clear();
+                                        app.visual_anchor = None;
+                                    }
                                     _ => {}
                                 }
                             },
+                            Mode::Visual => {
+                                if awaiting_register {
+                                    awaiting_register = false;
+                                    if let KeyCode::Char(c) = key.code {
+                                        visual_register = Some(c).filter(|&c| Registers::is_valid_name(c));
+                                    }
+                                    continue;
+                                }
+                                match key.code {
+                                    KeyCode::Char('"') => awaiting_register = true,
+                                    KeyCode::Esc | KeyCode::Char('V') => {
+                                        visual_register = None;
+                                        vim::exit_visual_mode(&mut app);
+                                    }
+                                    KeyCode::Char('j') => vim::next_clip(&mut app),
+                                    KeyCode::Char('k') => vim::previous_clip(&mut app),
+                                    KeyCode::Char('d') | KeyCode::Char('x') => vim::delete_selection(&mut app, visual_register.take()),
+                                    KeyCode::Char('y') => vim::yank_selection(&mut app, visual_register.take()),
+                                    KeyCode::Char('p') => vim::paste_over_selection(&mut app, visual_register.take()),
+                                    KeyCode::Char('>') => vim::shift_selection(&mut app, true),
+                                    KeyCode::Char('<') => vim::shift_selection(&mut app, false),
+                                    KeyCode::Char('c') => {
+                                        app.command_input = "comment ".to_string();
+                                        app.mode = Mode::Command;
+                                    }
+                                    KeyCode::Char(':') => app.mode = Mode::Command,
+                                    KeyCode::Char(' ') => vim::play_selection(&mut app),
+                                    _ => {}
+                                }
+                            }
+                            Mode::Cut => {
+                                // With words selected, `d` cuts them straight away, as in Visual mode.
+                                if key.code == KeyCode::Char('d') && app.word_anchor.is_some() {
+                                    cut::cut_selection(&mut app, 1);
+                                    continue;
+                                }
+                                let Some(command) = key_parser.feed(key) else { continue };
+                                let count = command.count_or_one();
+                                match command.action {
+                                    Action::Move(Motion::WordForward) => cut::move_word_cursor(&mut app, true, count),
+                                    Action::Move(Motion::WordBackward) => cut::move_word_cursor(&mut app, false, count),
+                                    Action::Operate(Operator::Delete, Motion::WordForward) => cut::cut_selection(&mut app, count),
+                                    Action::Key(key) => match key.code {
+                                        KeyCode::Esc => cut::exit_cut_mode(&mut app),
+                                        KeyCode::Char('v') => cut::toggle_word_selection(&mut app),
+                                        KeyCode::Char('x') => cut::cut_selection(&mut app, count),
+                                        KeyCode::Char('.') => cut::repeat_cut(&mut app, command.count),
+                                        KeyCode::Char('0') | KeyCode::Home => cut::move_word_cursor(&mut app, false, usize::MAX),
+                                        KeyCode::Char('$') | KeyCode::End => cut::move_word_cursor(&mut app, true, usize::MAX),
+                                        KeyCode::Char(' ') => vim::play_selection(&mut app),
+                                        _ => {}
+                                    },
+                                    _ => {}
+                                }
+                            }
+                            Mode::Split => {
+                                match key.code {
+                                    KeyCode::Esc => app.mode = Mode::Normal,
+                                    KeyCode::Char('w') => autofix::split_next_word(&mut app),
+                                    KeyCode::Char('b') => autofix::adjust_previous_word(&mut app),
+                                    KeyCode::Enter => autofix::confirm_split(&mut app),
+                                    _ => {}
+                                }
+                            }
                             Mode::Adjust => {
                                 match key.code {
                                     KeyCode::Esc => app.mode = Mode::Normal,
@@ -244,14 +398,21 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
                                     _ => {}
                                 }
                             }
-                            _ => {}
                         }
                     }
                 }
             },
-            Some(AppEvent::TranscriptionSuccess(clips, duration)) => app.load_clips(clips, duration),
+            Some(AppEvent::TranscriptionSuccess(clips, duration)) => {
+                app.load_clips(clips, duration);
+                swap::check_for_recovery(&mut app);
+            }
             Some(AppEvent::TranscriptionFailure(err_msg)) => app.set_error_state(err_msg),
             Some(AppEvent::StatusUpdate(msg)) => app.status_message = msg,
+            Some(AppEvent::AutosaveTick) => {
+                if let AppState::Ready = app.state {
+                    swap::maybe_write_swap(&mut app);
+                }
+            }
             None => break,
         }
 
@@ -261,6 +422,12 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     if let Some(pid) = app.playback_pid {
         sox::stop_playback(pid);
     }
+    // A clean exit doesn't need the swap file; only crashes should leave one.
+    if let AppState::Ready = app.state {
+        if app.pending_recovery.is_none() {
+            swap::delete_swap(&mut app);
+        }
+    }
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
     terminal.show_cursor()?;
@@ -0,0 +1,280 @@
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::{app::Clip, cache, sox, undo::UndoHistory};
+
+/// Bump this whenever the on-disk layout changes, and teach `migrate` how to
+/// bring the previous version forward.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AudioSource {
+    pub path: String,
+    /// SHA-256 of the audio content, see `cache::get_content_hash`.
+    #[serde(default)]
+    pub hash: String,
+    #[serde(default)]
+    pub duration: f64,
+    #[serde(default)]
+    pub sample_rate: u32,
+}
+
+impl AudioSource {
+    /// Probes the audio file for its hash, duration and sample rate. Missing
+    /// or unreadable media leaves those fields empty rather than failing.
+    pub fn probe(path: &str) -> AudioSource {
+        AudioSource {
+            path: path.to_string(),
+            hash: cache::get_content_hash(path).unwrap_or_default(),
+            duration: sox::get_duration(path).unwrap_or(0.0),
+            sample_rate: sox::get_sample_rate(path).unwrap_or(0),
+        }
+    }
+}
+
+/// Result of checking a project's audio against what it was saved with.
+pub enum MediaStatus {
+    Ok,
+    Missing,
+    Changed,
+}
+
+impl MediaStatus {
+    pub fn check(audio: &AudioSource) -> MediaStatus {
+        if !Path::new(&audio.path).is_file() {
+            return MediaStatus::Missing;
+        }
+        if audio.hash.is_empty() {
+            return MediaStatus::Ok;
+        }
+        match cache::get_content_hash(&audio.path) {
+            Ok(hash) if hash == audio.hash => MediaStatus::Ok,
+            _ => MediaStatus::Changed,
+        }
+    }
+
+    pub fn warning(&self, audio_path: &str) -> Option<String> {
+        match self {
+            MediaStatus::Ok => None,
+            MediaStatus::Missing => Some(format!("Audio file not found: {}. Use :relink <path> to locate it.", audio_path)),
+            MediaStatus::Changed => Some(format!("Audio file {} has changed since this project was saved. Use :relink <path> to confirm.", audio_path)),
+        }
+    }
+}
+
+/// An `.avim` project file. In memory `audio.path` is always usable from
+/// the current directory; on disk it is stored relative to the project file
+/// so projects can be moved or shared together with their media.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Project {
+    pub format_version: u32,
+    pub audio: AudioSource,
+    pub created_at: u64,
+    pub modified_at: u64,
+    pub clips: Vec<Clip>,
+    /// Undo/redo history as diffs against `clips`, so `u` and `Ctrl+r` work
+    /// across sessions. Absent in older projects and in swap files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo_history: Option<UndoHistory>,
+}
+
+impl Project {
+    pub fn load(path: &str) -> Result<Project, Box<dyn Error + Send + Sync>> {
+        let file_contents = fs::read_to_string(path)?;
+        let value: Value = serde_json::from_str(&file_contents)?;
+        let created_at = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or_else(cache::now_secs, |d| d.as_secs());
+        let mut project = migrate(value, created_at)?;
+        let stored_path = Path::new(&project.audio.path);
+        if stored_path.is_relative() {
+            let resolved = project_dir(path).join(stored_path);
+            // Legacy projects stored the path as typed, relative to the
+            // working directory; only prefer the project-relative location
+            // when that is where the file actually is.
+            if resolved.exists() || !stored_path.exists() {
+                project.audio.path = resolved.to_string_lossy().to_string();
+            }
+        }
+        Ok(project)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut on_disk = self.clone();
+        let audio_path = Path::new(&self.audio.path);
+        // Never store a path relative to the working directory: load resolves
+        // relative paths against the project, so it would grow on every save.
+        let stored = relative_path(&project_dir(path), audio_path).or_else(|| absolute_path(audio_path));
+        if let Some(stored) = stored {
+            on_disk.audio.path = stored.to_string_lossy().to_string();
+        }
+        let json_data = serde_json::to_string_pretty(&on_disk)?;
+        fs::write(path, json_data)?;
+        Ok(())
+    }
+}
+
+/// Brings any known project layout up to `FORMAT_VERSION`.
+///
+/// Version 0 is the original bare `(audio_path, clips)` tuple, which carries
+/// no metadata; its audio details are probed from the file on load and its
+/// timestamps fall back to the project file's modification time.
+fn migrate(value: Value, file_time: u64) -> Result<Project, Box<dyn Error + Send + Sync>> {
+    let version = match &value {
+        Value::Array(_) => 0,
+        Value::Object(map) => map.get("format_version").and_then(Value::as_u64).unwrap_or(0) as u32,
+        _ => return Err("Not an avim project file.".into()),
+    };
+
+    match version {
+        0 => {
+            let (audio_path, clips): (String, Vec<Clip>) = serde_json::from_value(value)?;
+            Ok(Project {
+                format_version: FORMAT_VERSION,
+                audio: AudioSource::probe(&audio_path),
+                created_at: file_time,
+                modified_at: file_time,
+                clips,
+                undo_history: None,
+            })
+        }
+        FORMAT_VERSION => Ok(serde_json::from_value(value)?),
+        newer => Err(format!(
+            "Project format version {} is newer than this avim supports ({}). Please upgrade avim.",
+            newer, FORMAT_VERSION
+        ).into()),
+    }
+}
+
+fn project_dir(project_path: &str) -> PathBuf {
+    match Path::new(project_path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// `target` expressed relative to `base_dir`, e.g. `../audio/interview.mp3`.
+/// Works for files that don't exist (yet), such as missing media. Returns
+/// `None` when either path cannot be resolved or they share no common root
+/// (different drives on Windows).
+fn relative_path(base_dir: &Path, target: &Path) -> Option<PathBuf> {
+    let base = absolute_path(base_dir)?;
+    let target = absolute_path(target)?;
+    let common = base.components().zip(target.components()).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return None;
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in base.components().skip(common) {
+        relative.push("..");
+    }
+    for component in target.components().skip(common) {
+        relative.push(component);
+    }
+    Some(relative)
+}
+
+/// `path` made absolute with `.` and `..` removed. Symlinks are resolved as
+/// far as the path exists, so a missing file lines up with its (canonical)
+/// directory.
+fn absolute_path(path: &Path) -> Option<PathBuf> {
+    let path = if path.is_absolute() { path.to_path_buf() } else { env::current_dir().ok()?.join(path) };
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => { normalized.pop(); }
+            other => normalized.push(other),
+        }
+    }
+
+    let mut existing = normalized.as_path();
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return Some(missing.iter().rev().fold(canonical, |path, name| path.join(name)));
+        }
+        missing.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn tuple_projects_migrate_to_the_current_version() {
+        let clip = Clip::for_test("hello there", 1.0, 2.0);
+        let value = json!(["no/such/audio.wav", [clip]]);
+        let project = migrate(value, 1_700_000_000).unwrap();
+        assert_eq!(project.format_version, FORMAT_VERSION);
+        assert_eq!(project.audio.path, "no/such/audio.wav");
+        assert_eq!(project.audio.hash, "");
+        assert_eq!((project.created_at, project.modified_at), (1_700_000_000, 1_700_000_000));
+        assert_eq!(project.clips.len(), 1);
+        assert_eq!(project.clips[0].transcript, "hello there");
+        assert_eq!(project.clips[0].words.len(), 2);
+    }
+
+    #[test]
+    fn current_version_loads_as_is_and_newer_versions_are_refused() {
+        let mut value = json!({
+            "format_version": FORMAT_VERSION,
+            "audio": { "path": "a.wav", "hash": "abc" },
+            "created_at": 1,
+            "modified_at": 2,
+            "clips": [],
+        });
+        let project = migrate(value.clone(), 99).unwrap();
+        assert_eq!((project.audio.hash.as_str(), project.created_at, project.modified_at), ("abc", 1, 2));
+
+        value["format_version"] = json!(FORMAT_VERSION + 1);
+        assert!(migrate(value, 99).is_err());
+        assert!(migrate(json!("not a project"), 99).is_err());
+    }
+
+    #[test]
+    fn missing_media_keeps_its_path_across_saves() {
+        // Relative to the working directory, like a path typed on the
+        // command line.
+        let dir = format!("target/avim_test_project_{}", std::process::id());
+        let project_path = format!("{}/sub/interview.avim", dir);
+        fs::create_dir_all(format!("{}/sub", dir)).unwrap();
+        let project = Project {
+            format_version: FORMAT_VERSION,
+            audio: AudioSource { path: format!("{}/media/missing.wav", dir), hash: String::new(), duration: 0.0, sample_rate: 0 },
+            created_at: 1,
+            modified_at: 1,
+            clips: Vec::new(),
+            undo_history: None,
+        };
+
+        let stored_path = |path: &str| -> String {
+            let value: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+            value["audio"]["path"].as_str().unwrap().to_string()
+        };
+        project.save(&project_path).unwrap();
+        let first = stored_path(&project_path);
+        let loaded = Project::load(&project_path).unwrap();
+        loaded.save(&project_path).unwrap();
+        let second = stored_path(&project_path);
+        let reloaded = Project::load(&project_path).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(Path::new(&first), Path::new("../media/missing.wav"));
+        assert_eq!(second, first);
+        assert_eq!(reloaded.audio.path, loaded.audio.path);
+        assert_eq!(absolute_path(Path::new(&loaded.audio.path)), absolute_path(Path::new(&project.audio.path)));
+    }
+}
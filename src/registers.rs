@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use crate::app::Clip;
+
+const NUMBERED_REGISTERS: usize = 9;
+
+/// Vim-style clip registers:
+///
+/// - `""` (unnamed) holds whatever was last deleted or yanked, and is what
+///   `p` uses when no register is given.
+/// - `"0` holds the last yank, and `"1`-`"9` the last nine deletions, most
+///   recent first. Deleting into a named register leaves them alone.
+/// - `"a`-`"z` are written only when named; `"A`-`"Z` append to them.
+#[derive(Debug, Default)]
+pub struct Registers {
+    unnamed: Vec<Clip>,
+    numbered: [Vec<Clip>; NUMBERED_REGISTERS + 1],
+    named: BTreeMap<char, Vec<Clip>>,
+}
+
+impl Registers {
+    pub fn is_valid_name(name: char) -> bool {
+        name == '"' || name.is_ascii_alphanumeric()
+    }
+
+    pub fn get(&self, name: Option<char>) -> &[Clip] {
+        match name {
+            None | Some('"') => &self.unnamed,
+            Some(digit @ '0'..='9') => &self.numbered[digit as usize - '0' as usize],
+            Some(letter) => self.named.get(&letter.to_ascii_lowercase()).map_or(&[], |clips| clips.as_slice()),
+        }
+    }
+
+    pub fn yank(&mut self, name: Option<char>, clips: Vec<Clip>) {
+        match name {
+            None | Some('"') => {
+                self.numbered[0] = clips.clone();
+                self.unnamed = clips;
+            }
+            Some(name) => self.write_named(name, clips),
+        }
+    }
+
+    pub fn delete(&mut self, name: Option<char>, clips: Vec<Clip>) {
+        match name {
+            None | Some('"') => {
+                self.numbered[1..].rotate_right(1);
+                self.numbered[1] = clips.clone();
+                self.unnamed = clips;
+            }
+            Some(name) => self.write_named(name, clips),
+        }
+    }
+
+    /// Writes an explicitly named register, appending for uppercase names.
+    /// The unnamed register then mirrors its new contents.
+    fn write_named(&mut self, name: char, clips: Vec<Clip>) {
+        let contents = match name {
+            '0'..='9' => {
+                let slot = &mut self.numbered[name as usize - '0' as usize];
+                *slot = clips;
+                slot.clone()
+            }
+            _ => {
+                let slot = self.named.entry(name.to_ascii_lowercase()).or_default();
+                if name.is_ascii_uppercase() {
+                    slot.extend(clips);
+                } else {
+                    *slot = clips;
+                }
+                slot.clone()
+            }
+        };
+        self.unnamed = contents;
+    }
+
+    /// Every non-empty register in display order: unnamed, numbered, named.
+    pub fn list(&self) -> Vec<(char, &[Clip])> {
+        let mut registers = vec![('"', self.unnamed.as_slice())];
+        registers.extend(self.numbered.iter().enumerate().map(|(i, clips)| ((b'0' + i as u8) as char, clips.as_slice())));
+        registers.extend(self.named.iter().map(|(&name, clips)| (name, clips.as_slice())));
+        registers.retain(|(_, clips)| !clips.is_empty());
+        registers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{test_clips as clips, transcripts};
+
+    #[test]
+    fn yank_fills_unnamed_and_zero() {
+        let mut registers = Registers::default();
+        registers.yank(None, clips(&["a", "b"]));
+        assert_eq!(transcripts(registers.get(None)), vec!["a", "b"]);
+        assert_eq!(transcripts(registers.get(Some('0'))), vec!["a", "b"]);
+        assert!(registers.get(Some('1')).is_empty());
+    }
+
+    #[test]
+    fn deletes_shift_through_the_numbered_registers() {
+        let mut registers = Registers::default();
+        for i in 1..=10 {
+            registers.delete(None, clips(&[&i.to_string()]));
+        }
+        assert_eq!(transcripts(registers.get(Some('1'))), vec!["10"]);
+        assert_eq!(transcripts(registers.get(Some('2'))), vec!["9"]);
+        assert_eq!(transcripts(registers.get(Some('9'))), vec!["2"]);
+        assert_eq!(transcripts(registers.get(None)), vec!["10"]);
+        assert!(registers.get(Some('0')).is_empty());
+    }
+
+    #[test]
+    fn uppercase_names_append() {
+        let mut registers = Registers::default();
+        registers.yank(Some('a'), clips(&["a"]));
+        registers.delete(Some('A'), clips(&["b", "c"]));
+        assert_eq!(transcripts(registers.get(Some('a'))), vec!["a", "b", "c"]);
+        assert_eq!(transcripts(registers.get(Some('A'))), vec!["a", "b", "c"]);
+        // The unnamed register mirrors the whole appended register.
+        assert_eq!(transcripts(registers.get(None)), vec!["a", "b", "c"]);
+
+        registers.yank(Some('a'), clips(&["d"]));
+        assert_eq!(transcripts(registers.get(Some('a'))), vec!["d"]);
+    }
+
+    #[test]
+    fn named_deletes_leave_numbered_registers_alone() {
+        let mut registers = Registers::default();
+        registers.delete(None, clips(&["first"]));
+        registers.delete(Some('x'), clips(&["second"]));
+        assert_eq!(transcripts(registers.get(Some('1'))), vec!["first"]);
+        assert!(registers.get(Some('2')).is_empty());
+    }
+
+    #[test]
+    fn list_skips_empty_registers() {
+        let mut registers = Registers::default();
+        registers.yank(None, clips(&["a"]));
+        registers.yank(Some('q'), clips(&["b"]));
+        let names: Vec<char> = registers.list().iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!['"', '0', 'q']);
+    }
+}
@@ -1,37 +1,68 @@
-use std::{env, fs, process::{Command, Stdio, Child}};
+use std::{env, fs, ops::Range, process::{Command, Stdio, Child}};
 use crate::app::App;
 
-pub fn play_clip_with_sox(path: &str, start: f64, end: f64) -> Result<Child, String> {
-    let duration = end - start;
+fn soxi(flag: &str, path: &str) -> Option<String> {
+    let output = Command::new("soxi").arg(flag).arg(path).stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+pub fn get_duration(path: &str) -> Option<f64> {
+    soxi("-D", path)?.parse().ok()
+}
+
+pub fn get_sample_rate(path: &str) -> Option<u32> {
+    soxi("-r", path)?.parse().ok()
+}
+
+/// Arguments for SoX's `trim` effect that keep only `segments` (absolute
+/// times in seconds, in order) and drop the audio around and between them.
+/// `trim` alternates between discarding and copying at each position.
+fn trim_args(segments: &[(f64, f64)]) -> Vec<String> {
+    let mut args = vec!["trim".to_string()];
+    for (start, end) in segments {
+        args.push(format!("={}", start));
+        args.push(format!("={}", end));
+    }
+    args
+}
+
+/// Plays the audible `segments` of a clip (see `Clip::audible_segments`).
+pub fn play_clip_with_sox(path: &str, segments: &[(f64, f64)]) -> Result<Child, String> {
+    if segments.is_empty() {
+        return Err("Nothing left to play in this clip.".to_string());
+    }
     Command::new("sox")
         .arg(path)
         .arg("-d") // Output to default audio device
-        .arg("trim")
-        .arg(start.to_string())
-        .arg(duration.to_string())
+        .args(trim_args(segments))
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .spawn()
         .map_err(|e| e.to_string())
 }
 
-pub fn play_playlist(app: &App, start_index: usize) -> Result<Child, String> {
+/// Plays `clips` of the transcript back to back.
+pub fn play_playlist(app: &App, clips: Range<usize>) -> Result<Child, String> {
     let temp_dir = env::temp_dir().join("avim_playlist");
     // Clean up old playlist files before creating new ones
     let _ = fs::remove_dir_all(&temp_dir);
     fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
 
     let mut temp_files = Vec::new();
-    for (i, clip) in app.clips.iter().skip(start_index).enumerate() {
+    for (i, clip) in app.clips[clips].iter().enumerate() {
+        let segments = clip.audible_segments();
+        if segments.is_empty() {
+            continue;
+        }
         let temp_filename = temp_dir.join(format!("playlist_clip_{}.wav", i));
-        let duration = clip.end_time - clip.start_time;
 
         let status = Command::new("sox")
             .arg(&app.original_audio_path)
             .arg(temp_filename.to_str().unwrap())
-            .arg("trim")
-            .arg(clip.start_time.to_string())
-            .arg(duration.to_string())
+            .args(trim_args(&segments))
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .status()
@@ -72,6 +103,59 @@ pub fn play_playlist(app: &App, start_index: usize) -> Result<Child, String> {
         .map_err(|e| e.to_string())
 }
 
+const ANALYSIS_SAMPLE_RATE: usize = 8000;
+const ANALYSIS_FRAME_SECONDS: f64 = 0.02;
+
+/// Finds the quietest 20ms frame within `search_radius` seconds of `target`
+/// and returns the time at its centre. The window is decoded to 8kHz mono so
+/// this stays cheap even on long files. Ties go to the frame closest to
+/// `target`.
+pub fn find_quietest_point(path: &str, target: f64, search_radius: f64) -> Result<f64, String> {
+    let window_start = (target - search_radius).max(0.0);
+    let window_length = target + search_radius - window_start;
+
+    let output = Command::new("sox")
+        .arg(path)
+        .arg("-t").arg("raw")
+        .arg("-r").arg(ANALYSIS_SAMPLE_RATE.to_string())
+        .arg("-c").arg("1")
+        .arg("-b").arg("16")
+        .arg("-e").arg("signed-integer")
+        .arg("-")
+        .arg("trim")
+        .arg(window_start.to_string())
+        .arg(window_length.to_string())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(format!("SoX failed to decode audio around {:.2}s", target));
+    }
+
+    let samples: Vec<f64> = output.stdout
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f64)
+        .collect();
+    let frame_size = (ANALYSIS_SAMPLE_RATE as f64 * ANALYSIS_FRAME_SECONDS) as usize;
+
+    let mut quietest: Option<(f64, f64)> = None;
+    for (i, frame) in samples.chunks_exact(frame_size).enumerate() {
+        let energy = frame.iter().map(|s| s * s).sum::<f64>() / frame_size as f64;
+        let time = window_start + (i as f64 + 0.5) * ANALYSIS_FRAME_SECONDS;
+        let is_better = match quietest {
+            None => true,
+            Some((best_energy, best_time)) => {
+                energy < best_energy || (energy == best_energy && (time - target).abs() < (best_time - target).abs())
+            }
+        };
+        if is_better {
+            quietest = Some((energy, time));
+        }
+    }
+
+    quietest.map(|(_, time)| time).ok_or_else(|| format!("No audio found around {:.2}s", target))
+}
 
 pub fn stop_playback(pid: u32) {
     Command::new("kill")
@@ -85,7 +169,13 @@ pub fn export_audio(app: &App, output_filename: &str) -> Result<(), Box<dyn std:
     let temp_dir = env::temp_dir().join("avim");
     fs::create_dir_all(&temp_dir)?;
 
-    if app.clips.is_empty() {
+    let clips: Vec<(usize, Vec<(f64, f64)>)> = app.clips.iter()
+        .map(|clip| clip.audible_segments())
+        .enumerate()
+        .filter(|(_, segments)| !segments.is_empty())
+        .collect();
+
+    if clips.is_empty() {
         Command::new("sox")
             .arg("-n")
             .arg("-r").arg("44100")
@@ -97,16 +187,13 @@ pub fn export_audio(app: &App, output_filename: &str) -> Result<(), Box<dyn std:
     }
 
     let mut temp_files = Vec::new();
-    for (i, clip) in app.clips.iter().enumerate() {
+    for (i, segments) in clips {
         let temp_filename = temp_dir.join(format!("clip_{}.wav", i));
-        let duration = clip.end_time - clip.start_time;
 
         let status = Command::new("sox")
             .arg(&app.original_audio_path)
             .arg(temp_filename.to_str().unwrap())
-            .arg("trim")
-            .arg(clip.start_time.to_string())
-            .arg(duration.to_string())
+            .args(trim_args(&segments))
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .status()?;
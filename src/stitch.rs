@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+use crate::app::Clip;
+
+/// Two clips from neighbouring chunks are treated as the same utterance when
+/// their word sets overlap at least this much and they overlap in time.
+const SIMILARITY_THRESHOLD: f64 = 0.6;
+/// A clip ending this close to its chunk's window end was probably cut off.
+const TRUNCATION_TOLERANCE_SECONDS: f64 = 0.5;
+
+/// Clips transcribed from one chunk window, already in file time.
+pub struct ChunkResult {
+    pub window_start: f64,
+    pub window_end: f64,
+    pub clips: Vec<Clip>,
+}
+
+/// Joins overlapping chunk results into one clip list.
+///
+/// Neighbouring windows share an overlap region. Each side keeps the clips
+/// that start on its half of that region; a clip the earlier chunk cut off
+/// at its window end is replaced by the later chunk's complete copy, and
+/// duplicates straddling the seam are collapsed. Timestamps come out
+/// monotonic.
+pub fn stitch_chunks(chunks: Vec<ChunkResult>) -> Vec<Clip> {
+    let mut stitched: Vec<Clip> = Vec::new();
+    let mut previous_window_end: Option<f64> = None;
+
+    for chunk in chunks {
+        let overlap_end = previous_window_end.unwrap_or(chunk.window_start);
+        let seam = if overlap_end > chunk.window_start {
+            (chunk.window_start + overlap_end) / 2.0
+        } else {
+            chunk.window_start
+        };
+
+        if previous_window_end.is_some() {
+            while stitched.last().is_some_and(|clip| clip.start_time >= seam) {
+                stitched.pop();
+            }
+        }
+
+        for clip in chunk.clips {
+            if clip.start_time < seam {
+                // The earlier chunk owns this part of the overlap. Only take
+                // this copy if the earlier one was truncated at its window end.
+                if let (Some(last), Some(window_end)) = (stitched.last_mut(), previous_window_end) {
+                    let truncated = last.end_time >= window_end - TRUNCATION_TOLERANCE_SECONDS;
+                    if truncated && clip.end_time > last.end_time && is_same_utterance(last, &clip) {
+                        *last = clip;
+                    }
+                }
+                continue;
+            }
+
+            if let Some(last) = stitched.last_mut() {
+                if is_same_utterance(last, &clip) {
+                    if clip.transcript.split_whitespace().count() > last.transcript.split_whitespace().count() {
+                        let start_time = last.start_time;
+                        *last = clip;
+                        last.start_time = start_time.min(last.start_time);
+                    }
+                    continue;
+                }
+            }
+            push_monotonic(&mut stitched, clip);
+        }
+
+        previous_window_end = Some(chunk.window_end);
+    }
+
+    stitched
+}
+
+/// Appends `clip`, pushing its start past the previous clip's end so
+/// timestamps never go backwards. Clips swallowed entirely are dropped.
+fn push_monotonic(clips: &mut Vec<Clip>, mut clip: Clip) {
+    if let Some(previous) = clips.last() {
+        if clip.start_time < previous.end_time {
+            clip.start_time = previous.end_time;
+            for word in &mut clip.words {
+                word.start = word.start.max(clip.start_time);
+                word.end = word.end.max(word.start);
+            }
+        }
+        if clip.end_time <= clip.start_time {
+            return;
+        }
+    }
+    clips.push(clip);
+}
+
+fn is_same_utterance(a: &Clip, b: &Clip) -> bool {
+    time_overlap(a, b) > 0.0 && text_similarity(&a.transcript, &b.transcript) >= SIMILARITY_THRESHOLD
+}
+
+fn time_overlap(a: &Clip, b: &Clip) -> f64 {
+    (a.end_time.min(b.end_time) - a.start_time.max(b.start_time)).max(0.0)
+}
+
+/// Word overlap relative to the shorter transcript, ignoring case and
+/// punctuation, so a truncated copy still matches its complete version.
+fn text_similarity(a: &str, b: &str) -> f64 {
+    let normalize = |text: &str| -> HashSet<String> {
+        text.split_whitespace()
+            .map(|w| w.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase())
+            .filter(|w| !w.is_empty())
+            .collect()
+    };
+    let a_words = normalize(a);
+    let b_words = normalize(b);
+    let shorter = a_words.len().min(b_words.len());
+    if shorter == 0 {
+        return 0.0;
+    }
+    a_words.intersection(&b_words).count() as f64 / shorter as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(window_start: f64, window_end: f64, clips: &[(&str, f64, f64)]) -> ChunkResult {
+        ChunkResult {
+            window_start,
+            window_end,
+            clips: clips.iter().map(|&(text, start, end)| Clip::for_test(text, start, end)).collect(),
+        }
+    }
+
+    fn summary(clips: &[Clip]) -> Vec<(&str, f64, f64)> {
+        clips.iter().map(|c| (c.transcript.as_str(), c.start_time, c.end_time)).collect()
+    }
+
+    #[test]
+    fn chunks_without_overlap_are_concatenated() {
+        let clips = stitch_chunks(vec![
+            chunk(0.0, 300.0, &[("one", 0.0, 10.0)]),
+            chunk(300.0, 600.0, &[("two", 300.0, 310.0)]),
+        ]);
+        assert_eq!(summary(&clips), vec![("one", 0.0, 10.0), ("two", 300.0, 310.0)]);
+    }
+
+    #[test]
+    fn truncated_clip_is_replaced_by_the_complete_copy() {
+        // Windows overlap on 300..310, so the seam is at 305.
+        let clips = stitch_chunks(vec![
+            chunk(0.0, 310.0, &[("before", 290.0, 300.0), ("hello there general", 303.0, 309.8)]),
+            chunk(300.0, 600.0, &[("hello there general kenobi", 303.1, 312.0), ("after", 312.0, 315.0)]),
+        ]);
+        assert_eq!(summary(&clips), vec![
+            ("before", 290.0, 300.0),
+            ("hello there general kenobi", 303.1, 312.0),
+            ("after", 312.0, 315.0),
+        ]);
+    }
+
+    #[test]
+    fn later_chunk_owns_clips_past_the_seam() {
+        let clips = stitch_chunks(vec![
+            chunk(0.0, 310.0, &[("early", 300.0, 304.0), ("cut off", 306.0, 310.0)]),
+            chunk(300.0, 600.0, &[("early", 300.2, 304.0), ("cut off words here", 306.1, 309.5)]),
+        ]);
+        assert_eq!(summary(&clips), vec![("early", 300.0, 304.0), ("cut off words here", 306.1, 309.5)]);
+    }
+
+    #[test]
+    fn overlapping_starts_are_pushed_forward() {
+        let clips = stitch_chunks(vec![chunk(0.0, 300.0, &[
+            ("first clip", 0.0, 5.0),
+            ("second clip", 4.0, 8.0),
+            ("swallowed", 6.0, 7.5),
+        ])]);
+        assert_eq!(summary(&clips), vec![("first clip", 0.0, 5.0), ("second clip", 5.0, 8.0)]);
+        assert!(clips[1].words.iter().all(|w| w.start >= 5.0 && w.end >= w.start));
+    }
+}
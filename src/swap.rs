@@ -0,0 +1,140 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use crate::{
+    app::App,
+    cache,
+    project::{self, AudioSource, Project},
+};
+
+/// How often unsaved edits are flushed to the swap file.
+pub const SWAP_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Where the swap file for this session lives. Projects get a hidden
+/// `.name.avim.swp` next to them, like Vim; sessions started from a bare
+/// audio file use `swap/<content hash>.swp` in the cache directory.
+pub fn get_swap_path(app: &App) -> Option<PathBuf> {
+    match &app.project_path {
+        Some(project_path) => {
+            let path = Path::new(project_path);
+            let file_name = path.file_name()?.to_string_lossy();
+            Some(path.with_file_name(format!(".{}.swp", file_name)))
+        }
+        None => {
+            let hash = cache::get_content_hash(&app.original_audio_path).ok()?;
+            let swap_dir = cache::get_cache_dir().ok()?.join("swap");
+            fs::create_dir_all(&swap_dir).ok()?;
+            Some(swap_dir.join(format!("{}.swp", hash)))
+        }
+    }
+}
+
+/// Writes the swap file if there are edits it doesn't have yet and the last
+/// write was at least `SWAP_INTERVAL` ago.
+pub fn maybe_write_swap(app: &mut App) {
+    if app.change_count == app.swap_change_count {
+        return;
+    }
+    if app.last_swap_write.is_some_and(|t| t.elapsed() < SWAP_INTERVAL) {
+        return;
+    }
+    write_swap(app);
+}
+
+pub fn write_swap(app: &mut App) {
+    let Some(swap_path) = get_swap_path(app) else { return };
+    let now = cache::now_secs();
+    let swap = Project {
+        format_version: project::FORMAT_VERSION,
+        audio: AudioSource {
+            path: app.original_audio_path.clone(),
+            hash: app.expected_audio_hash.clone().unwrap_or_default(),
+            duration: app.audio_duration,
+            sample_rate: 0,
+        },
+        created_at: app.project_created_at.unwrap_or(now),
+        modified_at: now,
+        clips: app.clips.clone(),
+        undo_history: None,
+    };
+    match swap.save(&swap_path.to_string_lossy()) {
+        Ok(_) => {
+            app.swap_change_count = app.change_count;
+            app.last_swap_write = Some(Instant::now());
+        }
+        Err(e) => app.log_debug(format!("Failed to write swap file: {}", e)),
+    }
+}
+
+/// Called once the session's clips are loaded. A swap file that is newer
+/// than the project (or any swap file for a bare audio session) means the
+/// previous session ended without saving; ask whether to recover it.
+pub fn check_for_recovery(app: &mut App) {
+    let Some(swap_path) = get_swap_path(app) else { return };
+    let Ok(swap_modified) = fs::metadata(&swap_path).and_then(|m| m.modified()) else { return };
+    let project_modified = app.project_path.as_ref()
+        .and_then(|p| fs::metadata(p).and_then(|m| m.modified()).ok());
+
+    if project_modified.is_some_and(|project_time| project_time >= swap_modified) {
+        fs::remove_file(&swap_path).ok();
+        return;
+    }
+    app.pending_recovery = Some(swap_path);
+}
+
+/// Replaces the loaded clips with the swap file's. The current clips go on
+/// the undo log, so `u` brings them back.
+pub fn recover(app: &mut App, swap_path: &Path) {
+    match Project::load(&swap_path.to_string_lossy()) {
+        Ok(swap) => {
+            app.set_clips(swap.clips);
+            app.current_clip_index = app.current_clip_index.min(app.clips.len().saturating_sub(1));
+            app.status_message = format!("Recovered {} clips from swap file. Use :w to save.", app.clips.len());
+        }
+        Err(e) => app.status_message = format!("Failed to read swap file: {}", e),
+    }
+}
+
+/// `i` at the recovery prompt: moves the swap file out of the way so this
+/// session's autosaves and clean exit don't destroy it. It is kept beside
+/// the original as `name.recovered-<unix time>.avim`, which avim can open
+/// like any project.
+pub fn set_aside(swap_path: &Path) -> std::io::Result<PathBuf> {
+    let file_name = swap_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let base = file_name.trim_start_matches('.').trim_end_matches(".swp").trim_end_matches(".avim");
+    let kept = swap_path.with_file_name(format!("{}.recovered-{}.avim", base, cache::now_secs()));
+    fs::rename(swap_path, &kept)?;
+    Ok(kept)
+}
+
+pub fn delete_swap(app: &mut App) {
+    if let Some(swap_path) = get_swap_path(app) {
+        fs::remove_file(swap_path).ok();
+    }
+    app.swap_change_count = app.change_count;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignored_swap_files_are_kept_as_projects() {
+        let dir = std::env::temp_dir().join(format!("avim_test_swap_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let swap_path = dir.join(".interview.avim.swp");
+        fs::write(&swap_path, "unsaved edits").unwrap();
+
+        let kept = set_aside(&swap_path).unwrap();
+        let kept_name = kept.file_name().unwrap().to_string_lossy().to_string();
+        let contents = fs::read_to_string(&kept).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert!(!swap_path.exists());
+        assert_eq!(kept.parent(), Some(dir.as_path()));
+        assert!(kept_name.starts_with("interview.recovered-") && kept_name.ends_with(".avim"), "{}", kept_name);
+        assert_eq!(contents, "unsaved edits");
+    }
+}
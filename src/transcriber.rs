@@ -0,0 +1,213 @@
+use std::{error::Error, fmt, future::Future, pin::Pin, sync::Arc, time::Duration};
+use crate::{
+    app::{Clip, Word},
+    config::Config,
+    gcp::GeminiTranscriber,
+    whisper::{WhisperCliTranscriber, WhisperServerTranscriber},
+};
+
+pub type TranscribeResult = Result<Vec<Clip>, TranscribeError>;
+pub type TranscribeFuture<'a> = Pin<Box<dyn Future<Output = TranscribeResult> + Send + 'a>>;
+
+#[derive(Debug)]
+pub enum TranscribeError {
+    /// Worth trying again: rate limiting, server errors, dropped connections
+    /// or a garbled model response. `retry_after` is the server's hint, if any.
+    Retryable { message: String, retry_after: Option<Duration> },
+    /// Retrying cannot help, e.g. a missing or rejected API key or audio the
+    /// backend refuses to decode.
+    Fatal(String),
+}
+
+impl TranscribeError {
+    pub fn retryable(message: impl Into<String>) -> Self {
+        TranscribeError::Retryable { message: message.into(), retry_after: None }
+    }
+
+    /// Classifies an HTTP error status: 408, 429 and 5xx are retryable,
+    /// everything else is fatal.
+    pub fn from_status(status: reqwest::StatusCode, retry_after: Option<Duration>, message: String) -> Self {
+        if status.as_u16() == 408 || status.as_u16() == 429 || status.is_server_error() {
+            TranscribeError::Retryable { message, retry_after }
+        } else {
+            TranscribeError::Fatal(message)
+        }
+    }
+}
+
+impl fmt::Display for TranscribeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscribeError::Retryable { message, .. } => write!(f, "{}", message),
+            TranscribeError::Fatal(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for TranscribeError {}
+
+impl From<reqwest::Error> for TranscribeError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() || e.is_connect() || e.is_request() || e.is_body() || e.is_decode() {
+            TranscribeError::retryable(e.to_string())
+        } else {
+            TranscribeError::Fatal(e.to_string())
+        }
+    }
+}
+
+impl From<std::io::Error> for TranscribeError {
+    fn from(e: std::io::Error) -> Self {
+        TranscribeError::Fatal(e.to_string())
+    }
+}
+
+impl From<String> for TranscribeError {
+    fn from(message: String) -> Self {
+        TranscribeError::Fatal(message)
+    }
+}
+
+/// Reads a `Retry-After` header given in seconds. HTTP dates and values
+/// that are negative or too large for a `Duration` are ignored.
+pub fn retry_after_header(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers.get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+}
+
+/// Where a chunk sits inside the full audio file.
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkContext {
+    pub index: usize,
+    pub total_chunks: usize,
+    pub start_time: f64,
+    pub duration: f64,
+}
+
+/// A transcription engine. Implementations receive one WAV chunk and return
+/// clips with timestamps relative to the start of that chunk; the chunking
+/// loop takes care of offsetting them into file time.
+pub trait Transcriber: Send + Sync {
+    fn name(&self) -> &str;
+    /// Identifies everything that shapes this backend's output (model,
+    /// prompt, options). Cached transcripts are only reused when it matches.
+    fn fingerprint(&self) -> String;
+    fn transcribe<'a>(&'a self, audio_data: &'a [u8], context: ChunkContext) -> TranscribeFuture<'a>;
+}
+
+pub fn from_config(config: &Config) -> Result<Arc<dyn Transcriber>, Box<dyn Error + Send + Sync>> {
+    match config.backend.as_str() {
+        "gemini" => Ok(Arc::new(GeminiTranscriber::new(config.gemini_model.clone()))),
+        "whisper" => Ok(Arc::new(WhisperCliTranscriber::new(
+            config.whisper_binary.clone(),
+            config.whisper_model.clone(),
+            config.whisper_diarize,
+        ))),
+        "whisper-server" => Ok(Arc::new(WhisperServerTranscriber::new(config.whisper_server_url.clone()))),
+        "fake" => Ok(Arc::new(FakeTranscriber)),
+        other => Err(format!(
+            "Unknown transcription backend '{}'. Available: gemini, whisper, whisper-server, fake",
+            other
+        ).into()),
+    }
+}
+
+const FAKE_CLIP_SECONDS: f64 = 10.0;
+
+/// Deterministic backend that never touches the network. It splits every
+/// chunk into fixed 10 second clips with alternating speakers, which makes
+/// it useful for exercising the editor and the chunking loop offline.
+pub struct FakeTranscriber;
+
+impl Transcriber for FakeTranscriber {
+    fn name(&self) -> &str {
+        "fake"
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("fake/{}s", FAKE_CLIP_SECONDS)
+    }
+
+    fn transcribe<'a>(&'a self, _audio_data: &'a [u8], context: ChunkContext) -> TranscribeFuture<'a> {
+        Box::pin(async move {
+            let num_clips = (context.duration / FAKE_CLIP_SECONDS).ceil() as usize;
+            let clips = (0..num_clips)
+                .map(|i| {
+                    let start_time = i as f64 * FAKE_CLIP_SECONDS;
+                    let end_time = (start_time + FAKE_CLIP_SECONDS).min(context.duration);
+                    let transcript = format!(
+                        "Chunk {} of {}, clip {} at {:.0} seconds.",
+                        context.index + 1, context.total_chunks, i + 1, context.start_time + start_time
+                    );
+                    let word_texts: Vec<&str> = transcript.split_whitespace().collect();
+                    let word_duration = (end_time - start_time) / word_texts.len() as f64;
+                    let words = word_texts.iter().enumerate()
+                        .map(|(w, text)| Word {
+                            text: text.to_string(),
+                            start: start_time + w as f64 * word_duration,
+                            end: start_time + (w + 1) as f64 * word_duration,
+                            confidence: Some(1.0),
+                        })
+                        .collect();
+                    Clip {
+                        id: i,
+                        speaker: format!("Speaker {}", i % 2 + 1),
+                        transcript,
+                        start_time,
+                        end_time,
+                        comment: String::new(),
+                        is_manually_adjusted: false,
+                        words,
+                        cuts: Vec::new(),
+                    }
+                })
+                .collect();
+            Ok(clips)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::{header::{HeaderMap, HeaderValue, RETRY_AFTER}, StatusCode};
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn rate_limits_timeouts_and_server_errors_are_retryable() {
+        for code in [408, 429, 500, 502, 503] {
+            let status = StatusCode::from_u16(code).unwrap();
+            let error = TranscribeError::from_status(status, Some(Duration::from_secs(2)), "busy".to_string());
+            assert!(
+                matches!(error, TranscribeError::Retryable { retry_after: Some(d), .. } if d == Duration::from_secs(2)),
+                "{} should be retryable", code
+            );
+        }
+    }
+
+    #[test]
+    fn other_client_errors_are_fatal() {
+        for code in [400, 401, 403, 404, 413] {
+            let status = StatusCode::from_u16(code).unwrap();
+            let error = TranscribeError::from_status(status, None, "no".to_string());
+            assert!(matches!(error, TranscribeError::Fatal(_)), "{} should be fatal", code);
+        }
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_only() {
+        assert_eq!(retry_after_header(&headers("7")), Some(Duration::from_secs(7)));
+        assert_eq!(retry_after_header(&headers(" 1.5 ")), Some(Duration::from_millis(1500)));
+        assert_eq!(retry_after_header(&headers("Wed, 21 Oct 2015 07:28:00 GMT")), None);
+        assert_eq!(retry_after_header(&headers("-3")), None);
+        assert_eq!(retry_after_header(&headers("1e300")), None);
+        assert_eq!(retry_after_header(&HeaderMap::new()), None);
+    }
+}
@@ -2,7 +2,7 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style, Modifier},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph, ListState},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, ListState},
     Frame,
 };
 use crate::app::{App, AppState, Mode};
@@ -31,6 +31,10 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     if app.debug_mode {
         render_debug_panel(f, app, main_chunks[1]);
     }
+
+    if app.info_panel.is_some() {
+        render_info_panel(f, app, f.size());
+    }
 }
 
 fn render_transcript_panel(f: &mut Frame, app: &mut App, area: Rect) {
@@ -44,55 +48,58 @@ fn render_transcript_panel(f: &mut Frame, app: &mut App, area: Rect) {
         }
         AppState::Ready => {
             let list_width = area.width.saturating_sub(6);
+            let selection = app.visual_anchor.map(|_| app.selected_range());
             let mut list_items = Vec::new();
             
+            // The clip with the word cursor and the words it highlights.
+            let word_cursor = match app.mode {
+                Mode::Adjust => Some((app.current_clip_index + 1, 0..=app.adjust_word_index)),
+                Mode::Split => Some((app.current_clip_index, 0..=app.adjust_word_index)),
+                Mode::Cut => {
+                    let anchor = app.word_anchor.unwrap_or(app.adjust_word_index);
+                    Some((app.current_clip_index, anchor.min(app.adjust_word_index)..=anchor.max(app.adjust_word_index)))
+                }
+                _ => None,
+            };
+
             for (i, clip) in app.clips.iter().enumerate() {
                 let line_number = format!("{:>4} ", i + 1);
                 let timestamp = format!("[{:0>5.2}-{:0>5.2}]", clip.start_time, clip.end_time);
-                
-                let mut full_text_spans = vec![
-                    Span::raw(timestamp.clone()),
-                    Span::raw(format!(" [{}] ", clip.speaker)),
-                ];
 
-                if app.mode == Mode::Adjust && i == app.current_clip_index + 1 {
-                    let words: Vec<&str> = clip.transcript.split_whitespace().collect();
-                    for (word_idx, word) in words.iter().enumerate() {
-                        let style = if word_idx <= app.adjust_word_index {
-                            Style::default().bg(Color::Yellow).fg(Color::Black)
-                        } else {
-                            Style::default()
-                        };
-                        full_text_spans.push(Span::styled(format!("{} ", word), style));
-                    }
+                let base_style = if i == app.current_clip_index {
+                    Style::default().fg(Color::Black).bg(Color::LightCyan)
+                } else if selection.as_ref().is_some_and(|range| range.contains(&i)) {
+                    Style::default().fg(Color::Black).bg(Color::LightBlue)
+                } else if app.mode == Mode::Adjust && i == app.current_clip_index + 1 {
+                    Style::default().add_modifier(Modifier::BOLD)
                 } else {
-                    full_text_spans.push(Span::raw(clip.transcript.clone()));
+                    Style::default()
+                };
+                let highlight = base_style.patch(Style::default().bg(Color::Yellow).fg(Color::Black));
+
+                let mut tokens = vec![
+                    (timestamp, base_style),
+                    (format!("[{}]", clip.speaker), base_style),
+                ];
+                if !clip.cuts.is_empty() {
+                    tokens.push((format!("[cut {:.1}s]", clip.cut_seconds()), Style::default().fg(Color::Red).patch(base_style)));
                 }
-                
-                let text_content_for_wrap = full_text_spans.iter().map(|s| s.content.as_ref()).collect::<String>();
-                let wrapped_lines = textwrap::wrap(&text_content_for_wrap, list_width as usize);
-                
-                let mut lines = Vec::new();
-                for (idx, line_str) in wrapped_lines.iter().enumerate() {
-                     let prefix = if idx == 0 { line_number.clone() } else { "     ".to_string() };
-                     lines.push(Line::from(vec![Span::raw(prefix), Span::raw(line_str.to_string())]));
+                for (word_idx, word) in clip.transcript.split_whitespace().enumerate() {
+                    let highlighted = word_cursor.as_ref().is_some_and(|(clip, words)| *clip == i && words.contains(&word_idx));
+                    let style = if highlighted { highlight } else { base_style };
+                    tokens.push((word.to_string(), style));
                 }
-                
+
+                let mut lines = wrap_tokens(tokens, &line_number, list_width as usize, base_style);
+
                 if !clip.comment.is_empty() {
                     lines.push(Line::from(Span::styled(
                         format!("     // {}", clip.comment),
-                        Style::default().fg(Color::Green),
+                        Style::default().fg(Color::Green).patch(base_style),
                     )));
                 }
-                
-                let text = if i == app.current_clip_index {
-                    Text::from(lines).patch_style(Style::default().fg(Color::Black).bg(Color::LightCyan))
-                } else if app.mode == Mode::Adjust && i == app.current_clip_index + 1 {
-                    Text::from(lines).patch_style(Style::default().add_modifier(Modifier::BOLD))
-                } else {
-                    Text::from(lines)
-                };
-                list_items.push(ListItem::new(text));
+
+                list_items.push(ListItem::new(Text::from(lines)));
             }
 
             let list_title = format!("Transcript ({} clips)", app.clips.len());
@@ -106,6 +113,38 @@ fn render_transcript_panel(f: &mut Frame, app: &mut App, area: Rect) {
     }
 }
 
+/// Greedily wraps space-separated tokens to `width`, keeping each token's
+/// style so that word highlights survive wrapping. Tokens wider than a line
+/// (long URLs) are hard-split. The first line starts with `line_number`,
+/// the rest are indented to match.
+fn wrap_tokens(tokens: Vec<(String, Style)>, line_number: &str, width: usize, base_style: Style) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let indent = " ".repeat(line_number.len());
+    let mut lines = Vec::new();
+    let mut spans = vec![Span::styled(line_number.to_string(), base_style)];
+    let mut line_width = 0;
+
+    let pieces = tokens.into_iter().flat_map(|(text, style)| {
+        let chars: Vec<char> = text.chars().collect();
+        chars.chunks(width).map(|piece| (piece.iter().collect::<String>(), style)).collect::<Vec<_>>()
+    });
+    for (text, style) in pieces {
+        let token_width = text.chars().count();
+        if line_width > 0 && line_width + 1 + token_width > width {
+            lines.push(Line::from(std::mem::replace(&mut spans, vec![Span::styled(indent.clone(), base_style)])));
+            line_width = 0;
+        }
+        if line_width > 0 {
+            spans.push(Span::styled(" ", base_style));
+            line_width += 1;
+        }
+        spans.push(Span::styled(text, style));
+        line_width += token_width;
+    }
+    lines.push(Line::from(spans));
+    lines
+}
+
 fn render_status_panel(f: &mut Frame, app: &mut App, area: Rect) {
     let status_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -115,26 +154,53 @@ fn render_status_panel(f: &mut Frame, app: &mut App, area: Rect) {
     let mode_text = match app.mode {
         Mode::Normal => "-- NORMAL --".to_string(),
         Mode::Command => format!(":{}", app.command_input),
-        Mode::Insert => "-- INSERT --".to_string(),
+        Mode::Insert => match &app.insert {
+            Some(session) => format!("-- INSERT ({}) --  Tab: switch field, Esc: done", session.field.label()),
+            None => "-- INSERT --".to_string(),
+        },
         Mode::Adjust => "-- ADJUST --".to_string(),
-        Mode::Visual => "-- VISUAL --".to_string(),
+        Mode::Split => "-- SPLIT --".to_string(),
+        Mode::Cut if app.word_anchor.is_some() => "-- CUT (selecting words) --".to_string(),
+        Mode::Cut => "-- CUT --".to_string(),
+        Mode::Visual => format!("-- VISUAL LINE -- ({} selected)", app.selected_range().len()),
     };
     let mode_bar = Paragraph::new(mode_text)
         .style(Style::default().fg(Color::White).bg(Color::DarkGray));
     f.render_widget(mode_bar, status_chunks[0]);
 
-    match app.mode {
-        Mode::Command => {
-            f.set_cursor(
-                status_chunks[0].x + 1 + app.command_input.len() as u16,
-                status_chunks[0].y,
-            );
-        }
-        _ => {}
+    if app.mode == Mode::Command {
+        f.set_cursor(
+            status_chunks[0].x + 1 + app.command_input.len() as u16,
+            status_chunks[0].y,
+        );
+    }
+
+    if let (Mode::Insert, Some(session)) = (app.mode, &app.insert) {
+        let prefix = format!("{}: ", session.field.label());
+        let prefix_width = prefix.chars().count();
+        let available = (status_chunks[1].width as usize).saturating_sub(prefix_width + 1).max(1);
+        let cursor = session.editor.cursor();
+        let scroll = cursor.saturating_sub(available);
+        let visible: String = session.editor.text().chars().skip(scroll).take(available + 1).collect();
+        let editor_line = Paragraph::new(Line::from(vec![
+            Span::styled(prefix, Style::default().fg(Color::Yellow)),
+            Span::raw(visible),
+        ]));
+        f.render_widget(editor_line, status_chunks[1]);
+        f.set_cursor(
+            status_chunks[1].x + (prefix_width + cursor - scroll) as u16,
+            status_chunks[1].y,
+        );
+        return;
     }
 
-    let message_bar = Paragraph::new(app.status_message.as_str())
-        .style(Style::default().fg(Color::White));
+    let message_bar = if app.pending_recovery.is_some() {
+        Paragraph::new("Found unsaved changes from a previous session. (r)ecover, (d)elete swap file, (i)gnore")
+            .style(Style::default().fg(Color::Black).bg(Color::Yellow))
+    } else {
+        Paragraph::new(app.status_message.as_str())
+            .style(Style::default().fg(Color::White))
+    };
     f.render_widget(message_bar, status_chunks[1]);
 }
 
@@ -149,3 +215,43 @@ fn render_debug_panel(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(log_list, area);
 }
 
+
+fn render_info_panel(f: &mut Frame, app: &App, area: Rect) {
+    let Some(panel) = &app.info_panel else { return };
+
+    let height = (panel.lines.len() as u16 + 4).min(area.height);
+    let width = area.width.saturating_sub(4).min(
+        panel.lines.iter().map(|l| l.len()).max().unwrap_or(0).max(panel.title.len()) as u16 + 4
+    );
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    let mut lines: Vec<Line> = panel.lines.iter().map(|l| Line::from(l.as_str())).collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Press any key to close", Style::default().fg(Color::DarkGray))));
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(panel.title.as_str()));
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect()).collect()
+    }
+
+    #[test]
+    fn long_tokens_are_hard_split_across_lines() {
+        let tokens = ["see", "abcdefghij", "ok"].iter().map(|t| (t.to_string(), Style::default())).collect();
+        let lines = wrap_tokens(tokens, "1 ", 4, Style::default());
+        assert_eq!(texts(&lines), vec!["1 see", "  abcd", "  efgh", "  ij", "  ok"]);
+    }
+}
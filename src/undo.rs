@@ -0,0 +1,568 @@
+use serde::{Deserialize, Serialize};
+use crate::{app::Clip, cache::now_secs};
+
+/// Undo states kept in memory and in the project file; older ones are dropped.
+pub const MAX_UNDO_STEPS: usize = 500;
+
+/// One edit as a splice: at `index`, the clips in `removed` were replaced by
+/// the clips in `inserted`. Storing only the affected clips keeps history
+/// small even for transcripts with thousands of clips.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Change {
+    pub index: usize,
+    pub removed: Vec<Clip>,
+    pub inserted: Vec<Clip>,
+}
+
+impl Change {
+    /// The smallest splice turning `before` into `after`, found by trimming
+    /// their common prefix and suffix.
+    pub fn diff(before: &[Clip], after: &[Clip]) -> Change {
+        let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+        let suffix = before[prefix..].iter().rev()
+            .zip(after[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Change {
+            index: prefix,
+            removed: before[prefix..before.len() - suffix].to_vec(),
+            inserted: after[prefix..after.len() - suffix].to_vec(),
+        }
+    }
+
+    /// Re-does the change. Returns false, leaving `clips` untouched, if the
+    /// change doesn't fit (e.g. history from a different transcript).
+    pub fn apply(&self, clips: &mut Vec<Clip>) -> bool {
+        splice(clips, self.index, &self.removed, &self.inserted)
+    }
+
+    /// Undoes the change, with the same safety check as `apply`.
+    pub fn revert(&self, clips: &mut Vec<Clip>) -> bool {
+        splice(clips, self.index, &self.inserted, &self.removed)
+    }
+}
+
+fn splice(clips: &mut Vec<Clip>, index: usize, expected: &[Clip], replacement: &[Clip]) -> bool {
+    let end = index + expected.len();
+    if end > clips.len() || clips[index..end] != *expected {
+        return false;
+    }
+    clips.splice(index..end, replacement.iter().cloned());
+    true
+}
+
+/// Edits that merge into the previous undo step when repeated on the same
+/// clip, so an Insert mode session or a run of nudges is undone in one go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coalesce {
+    Insert(usize),
+    StartTime(usize),
+    EndTime(usize),
+}
+
+/// One state in the undo tree, reached from `parent` by applying `change`.
+/// `seq` numbers states in the order they were created, as in Vim.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UndoNode {
+    pub seq: u64,
+    pub parent: usize,
+    pub change: Change,
+    /// Unix time of the last edit that went into this state.
+    pub time: u64,
+    /// The child `redo` follows: the branch most recently created or visited.
+    #[serde(default)]
+    pub last_child: Option<usize>,
+}
+
+/// Branching undo history. Node 0 is the oldest state still kept; every
+/// other node is one edit away from its parent. Editing after an undo starts
+/// a new branch instead of discarding the undone one, and `goto` can move
+/// between any two states by undoing up to their common ancestor and
+/// redoing down the other branch.
+#[derive(Debug)]
+pub struct UndoTree {
+    nodes: Vec<UndoNode>,
+    current: usize,
+    coalesce: Option<Coalesce>,
+}
+
+impl Default for UndoTree {
+    fn default() -> Self {
+        UndoTree { nodes: vec![root_node(0, now_secs())], current: 0, coalesce: None }
+    }
+}
+
+fn root_node(seq: u64, time: u64) -> UndoNode {
+    UndoNode {
+        seq,
+        parent: 0,
+        change: Change { index: 0, removed: Vec::new(), inserted: Vec::new() },
+        time,
+        last_child: None,
+    }
+}
+
+/// A leaf of the undo tree as listed by `:undolist`.
+pub struct UndoBranch {
+    pub seq: u64,
+    pub changes: usize,
+    pub time: u64,
+    pub is_current: bool,
+}
+
+impl UndoTree {
+    /// Records an edit that has already been made to the clips as a new
+    /// child of the current state. Consecutive edits with the same
+    /// `coalesce` key are merged into one state.
+    pub fn record(&mut self, change: Change, coalesce: Option<Coalesce>) {
+        if change.removed == change.inserted {
+            return;
+        }
+        let current = &mut self.nodes[self.current];
+        if let Some(key) = coalesce {
+            if self.coalesce == Some(key) && current.last_child.is_none()
+                && current.change.index == change.index && current.change.inserted == change.removed
+            {
+                current.change.inserted = change.inserted;
+                current.time = now_secs();
+                return;
+            }
+        }
+        let seq = self.nodes.iter().map(|n| n.seq).max().unwrap_or(0) + 1;
+        self.nodes.push(UndoNode { seq, parent: self.current, change, time: now_secs(), last_child: None });
+        let new = self.nodes.len() - 1;
+        self.nodes[self.current].last_child = Some(new);
+        self.current = new;
+        self.coalesce = coalesce;
+        self.prune();
+    }
+
+    /// Ends the current coalescing run, e.g. when entering Insert mode.
+    pub fn seal(&mut self) {
+        self.coalesce = None;
+    }
+
+    /// True until the first edit.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    pub fn current_seq(&self) -> u64 {
+        self.nodes[self.current].seq
+    }
+
+    pub fn current_time(&self) -> u64 {
+        self.nodes[self.current].time
+    }
+
+    /// Moves to the parent state. Returns the clip index that changed.
+    pub fn undo(&mut self, clips: &mut Vec<Clip>) -> Option<usize> {
+        if self.current == 0 {
+            return None;
+        }
+        self.goto(self.nodes[self.current].parent, clips)
+    }
+
+    /// Moves to the most recently used child state.
+    pub fn redo(&mut self, clips: &mut Vec<Clip>) -> Option<usize> {
+        let child = self.nodes[self.current].last_child?;
+        self.goto(child, clips)
+    }
+
+    /// Moves `steps` states back (negative) or forward in creation order,
+    /// regardless of branch, like Vim's `g-` and `g+`. Steps count the
+    /// states that still exist, so gaps left by pruning are skipped.
+    pub fn step_chronologically(&mut self, steps: i64, clips: &mut Vec<Clip>) -> Option<usize> {
+        let mut order: Vec<usize> = (0..self.nodes.len()).collect();
+        order.sort_by_key(|&i| self.nodes[i].seq);
+        let position = order.iter().position(|&i| i == self.current)?;
+        let distance = usize::try_from(steps.unsigned_abs()).unwrap_or(usize::MAX);
+        let target = if steps < 0 {
+            position.saturating_sub(distance)
+        } else {
+            position.saturating_add(distance).min(order.len() - 1)
+        };
+        if target == position {
+            return None;
+        }
+        self.goto(order[target], clips)
+    }
+
+    /// Moves to the newest state created at or before `time`; the oldest
+    /// kept state if every edit is newer.
+    pub fn goto_time(&mut self, time: u64, clips: &mut Vec<Clip>) -> Option<usize> {
+        let target = self.nodes.iter().enumerate()
+            .filter(|(i, n)| *i == 0 || n.time <= time)
+            .max_by_key(|(_, n)| n.seq)
+            .map(|(i, _)| i)?;
+        if target == self.current {
+            return None;
+        }
+        self.goto(target, clips)
+    }
+
+    /// The tips of all branches, oldest first, for `:undolist`.
+    pub fn branches(&self) -> Vec<UndoBranch> {
+        let mut leaves: Vec<UndoBranch> = (0..self.nodes.len())
+            .filter(|&i| !self.nodes.iter().enumerate().any(|(j, n)| j != 0 && n.parent == i))
+            .map(|i| UndoBranch {
+                seq: self.nodes[i].seq,
+                changes: self.depth(i),
+                time: self.nodes[i].time,
+                is_current: i == self.current,
+            })
+            .collect();
+        leaves.sort_by_key(|leaf| leaf.seq);
+        leaves
+    }
+
+    fn depth(&self, mut node: usize) -> usize {
+        let mut depth = 0;
+        while node != 0 {
+            node = self.nodes[node].parent;
+            depth += 1;
+        }
+        depth
+    }
+
+    fn ancestors(&self, mut node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        while node != 0 {
+            node = self.nodes[node].parent;
+            path.push(node);
+        }
+        path
+    }
+
+    /// Walks from the current state to `target`, reverting changes up to
+    /// their common ancestor and applying them down to `target`. Returns the
+    /// clip index of the last change touched.
+    fn goto(&mut self, target: usize, clips: &mut Vec<Clip>) -> Option<usize> {
+        self.coalesce = None;
+        let target_path = self.ancestors(target);
+        let mut touched = None;
+        while !target_path.contains(&self.current) {
+            let node = &self.nodes[self.current];
+            if !node.change.revert(clips) {
+                return self.reset();
+            }
+            touched = Some(node.change.index);
+            let parent = node.parent;
+            self.nodes[parent].last_child = Some(self.current);
+            self.current = parent;
+        }
+        let ancestor = target_path.iter().position(|&n| n == self.current).unwrap_or(0);
+        for &node in target_path[..ancestor].iter().rev() {
+            if !self.nodes[node].change.apply(clips) {
+                return self.reset();
+            }
+            touched = Some(self.nodes[node].change.index);
+            self.nodes[self.current].last_child = Some(node);
+            self.current = node;
+        }
+        touched
+    }
+
+    /// Drops a history that no longer matches the clips, keeping them as
+    /// they are.
+    fn reset(&mut self) -> Option<usize> {
+        *self = UndoTree::default();
+        None
+    }
+
+    /// Keeps the tree at `MAX_UNDO_STEPS` states by moving the root forward
+    /// along the current branch. Branches that split off before the new root
+    /// are dropped with it.
+    fn prune(&mut self) {
+        while self.nodes.len() > MAX_UNDO_STEPS + 1 && self.current != 0 {
+            let path = self.ancestors(self.current);
+            let new_root = path[path.len() - 2];
+            let mut keep = vec![false; self.nodes.len()];
+            keep[new_root] = true;
+            for i in 0..self.nodes.len() {
+                // Parents always come before their children.
+                if i != new_root && i != 0 && keep[self.nodes[i].parent] {
+                    keep[i] = true;
+                }
+            }
+            let mut remap = vec![0; self.nodes.len()];
+            let mut nodes = Vec::with_capacity(self.nodes.len());
+            for (i, node) in self.nodes.drain(..).enumerate() {
+                if keep[i] {
+                    remap[i] = nodes.len();
+                    nodes.push(node);
+                }
+            }
+            for node in &mut nodes {
+                node.parent = remap[node.parent];
+                node.last_child = node.last_child.filter(|&c| keep[c]).map(|c| remap[c]);
+            }
+            let (seq, time, last_child) = (nodes[0].seq, nodes[0].time, nodes[0].last_child);
+            nodes[0] = UndoNode { last_child, ..root_node(seq, time) };
+            self.current = remap[self.current];
+            self.nodes = nodes;
+        }
+    }
+
+    pub fn to_history(&self) -> UndoHistory {
+        UndoHistory { nodes: self.nodes.clone(), current: self.current }
+    }
+
+    /// Restores a saved tree whose current state is `current`. Every change
+    /// is replayed on a copy of the clips first; if any fails to line up the
+    /// history belongs to different clips and an empty tree is returned.
+    pub fn from_history(history: UndoHistory, current: &[Clip]) -> UndoTree {
+        let tree = UndoTree { nodes: history.nodes, current: history.current, coalesce: None };
+        if tree.is_consistent(current) { tree } else { UndoTree::default() }
+    }
+
+    fn is_consistent(&self, current: &[Clip]) -> bool {
+        let len = self.nodes.len();
+        if len == 0 || self.current >= len
+            || self.nodes.iter().enumerate().skip(1).any(|(i, n)| n.parent >= i)
+            || self.nodes.iter().any(|n| n.last_child.is_some_and(|c| c >= len))
+        {
+            return false;
+        }
+        let mut clips = current.to_vec();
+        if !self.ancestors(self.current).iter().all(|&n| n == 0 || self.nodes[n].change.revert(&mut clips)) {
+            return false;
+        }
+        // Depth-first walk from the root, applying each change on the way
+        // down and reverting it on the way back up.
+        let mut children = vec![Vec::new(); len];
+        for i in 1..len {
+            children[self.nodes[i].parent].push(i);
+        }
+        let mut stack = vec![(0, false)];
+        while let Some((node, leaving)) = stack.pop() {
+            if leaving {
+                if node != 0 && !self.nodes[node].change.revert(&mut clips) {
+                    return false;
+                }
+                continue;
+            }
+            if node != 0 && !self.nodes[node].change.apply(&mut clips) {
+                return false;
+            }
+            stack.push((node, true));
+            stack.extend(children[node].iter().map(|&child| (child, false)));
+        }
+        true
+    }
+}
+
+/// Describes how long ago `time` was, e.g. "5 minutes ago".
+pub fn format_age(time: u64) -> String {
+    let secs = now_secs().saturating_sub(time);
+    let (amount, unit) = match secs {
+        0..=59 => (secs, "second"),
+        60..=3_599 => (secs / 60, "minute"),
+        3_600..=86_399 => (secs / 3_600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    format!("{} {}{} ago", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+/// The undo tree as stored in a project file. Histories written before undo
+/// branched have neither field and load as empty.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UndoHistory {
+    #[serde(default)]
+    pub nodes: Vec<UndoNode>,
+    #[serde(default)]
+    pub current: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{test_clips as clips, transcripts};
+
+    /// Makes an edit the way `App::set_clips` does: diff, record, replace.
+    fn edit(tree: &mut UndoTree, current: &mut Vec<Clip>, texts: &[&str], coalesce: Option<Coalesce>) {
+        let after = clips(texts);
+        tree.record(Change::diff(current, &after), coalesce);
+        *current = after;
+    }
+
+    #[test]
+    fn diff_trims_common_prefix_and_suffix() {
+        let change = Change::diff(&clips(&["a", "b", "c", "d"]), &clips(&["a", "x", "y", "c", "d"]));
+        assert_eq!(change.index, 1);
+        assert_eq!(transcripts(&change.removed), vec!["b"]);
+        assert_eq!(transcripts(&change.inserted), vec!["x", "y"]);
+    }
+
+    #[test]
+    fn diff_of_equal_lists_is_empty() {
+        let change = Change::diff(&clips(&["a", "b"]), &clips(&["a", "b"]));
+        assert!(change.removed.is_empty() && change.inserted.is_empty());
+    }
+
+    #[test]
+    fn apply_and_revert_check_the_clips_they_replace() {
+        let before = clips(&["a", "b"]);
+        let change = Change::diff(&before, &clips(&["a", "c"]));
+        let mut current = before.clone();
+        assert!(change.apply(&mut current));
+        assert_eq!(transcripts(&current), vec!["a", "c"]);
+        assert!(!change.apply(&mut current));
+        assert!(change.revert(&mut current));
+        assert_eq!(current, before);
+    }
+
+    #[test]
+    fn undo_and_redo_walk_one_branch() {
+        let mut tree = UndoTree::default();
+        let mut current = clips(&["a"]);
+        edit(&mut tree, &mut current, &["b"], None);
+        edit(&mut tree, &mut current, &["c"], None);
+
+        assert_eq!(tree.undo(&mut current), Some(0));
+        assert_eq!(transcripts(&current), vec!["b"]);
+        tree.undo(&mut current);
+        assert_eq!(transcripts(&current), vec!["a"]);
+        assert_eq!(tree.undo(&mut current), None);
+
+        tree.redo(&mut current);
+        tree.redo(&mut current);
+        assert_eq!(transcripts(&current), vec!["c"]);
+        assert_eq!(tree.redo(&mut current), None);
+    }
+
+    #[test]
+    fn editing_after_undo_starts_a_branch() {
+        let mut tree = UndoTree::default();
+        let mut current = clips(&["a"]);
+        edit(&mut tree, &mut current, &["b"], None);
+        tree.undo(&mut current);
+        edit(&mut tree, &mut current, &["c"], None);
+
+        let branches = tree.branches();
+        assert_eq!(branches.iter().map(|b| (b.seq, b.changes, b.is_current)).collect::<Vec<_>>(), vec![(1, 1, false), (2, 1, true)]);
+
+        // Redo follows the newest branch.
+        tree.undo(&mut current);
+        tree.redo(&mut current);
+        assert_eq!(transcripts(&current), vec!["c"]);
+    }
+
+    #[test]
+    fn chronological_steps_cross_branches() {
+        let mut tree = UndoTree::default();
+        let mut current = clips(&["a"]);
+        edit(&mut tree, &mut current, &["b"], None);
+        tree.undo(&mut current);
+        edit(&mut tree, &mut current, &["c"], None);
+
+        // g- from state 2 goes to state 1 on the other branch, then to the root.
+        tree.step_chronologically(-1, &mut current);
+        assert_eq!((tree.current_seq(), transcripts(&current)), (1, vec!["b"]));
+        tree.step_chronologically(-1, &mut current);
+        assert_eq!((tree.current_seq(), transcripts(&current)), (0, vec!["a"]));
+        assert_eq!(tree.step_chronologically(-1, &mut current), None);
+
+        // g+ twice comes back to the newest state.
+        tree.step_chronologically(2, &mut current);
+        assert_eq!((tree.current_seq(), transcripts(&current)), (2, vec!["c"]));
+    }
+
+    #[test]
+    fn huge_chronological_steps_stop_at_the_ends() {
+        let mut tree = UndoTree::default();
+        let mut current = clips(&["a"]);
+        edit(&mut tree, &mut current, &["b"], None);
+        edit(&mut tree, &mut current, &["c"], None);
+
+        assert_eq!(tree.step_chronologically(i64::MAX, &mut current), None);
+        tree.step_chronologically(i64::MIN, &mut current);
+        assert_eq!((tree.current_seq(), transcripts(&current)), (0, vec!["a"]));
+        tree.step_chronologically(i64::MAX, &mut current);
+        assert_eq!((tree.current_seq(), transcripts(&current)), (2, vec!["c"]));
+    }
+
+    #[test]
+    fn coalesced_edits_undo_together_until_sealed() {
+        let mut tree = UndoTree::default();
+        let mut current = clips(&["a"]);
+        edit(&mut tree, &mut current, &["a1"], Some(Coalesce::Insert(0)));
+        edit(&mut tree, &mut current, &["a12"], Some(Coalesce::Insert(0)));
+        tree.seal();
+        edit(&mut tree, &mut current, &["a123"], Some(Coalesce::Insert(0)));
+
+        tree.undo(&mut current);
+        assert_eq!(transcripts(&current), vec!["a12"]);
+        tree.undo(&mut current);
+        assert_eq!(transcripts(&current), vec!["a"]);
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_steps_on_the_current_branch() {
+        let mut tree = UndoTree::default();
+        let mut current = clips(&["0"]);
+        // A side branch off the first state, dropped once the root passes it.
+        edit(&mut tree, &mut current, &["side"], None);
+        tree.undo(&mut current);
+        for i in 1..=MAX_UNDO_STEPS + 10 {
+            edit(&mut tree, &mut current, &[&i.to_string()], None);
+        }
+        assert_eq!(tree.nodes.len(), MAX_UNDO_STEPS + 1);
+        assert_eq!(tree.branches().len(), 1);
+
+        let mut undone = 0;
+        while tree.undo(&mut current).is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_UNDO_STEPS);
+        assert_eq!(transcripts(&current), vec!["10"]);
+    }
+
+    #[test]
+    fn chronological_steps_skip_pruned_states() {
+        let mut tree = UndoTree::default();
+        let mut current = clips(&["0"]);
+        edit(&mut tree, &mut current, &["1"], None);
+        edit(&mut tree, &mut current, &["2"], None);
+        edit(&mut tree, &mut current, &["3"], None);
+        // A branch off state 1 (seq 4), then back to state 3 to carry on.
+        tree.undo(&mut current);
+        tree.undo(&mut current);
+        edit(&mut tree, &mut current, &["side"], None);
+        tree.step_chronologically(-1, &mut current);
+        assert_eq!(transcripts(&current), vec!["3"]);
+        // Enough edits for the root to pass state 1, which drops the branch
+        // and leaves a gap between seq 3 and seq 5.
+        for i in 0..MAX_UNDO_STEPS - 2 {
+            edit(&mut tree, &mut current, &[&format!("main {}", i)], None);
+        }
+        assert_eq!(tree.branches().len(), 1);
+
+        let mut steps = 0;
+        while tree.step_chronologically(-1, &mut current).is_some() {
+            steps += 1;
+        }
+        assert_eq!(steps, tree.nodes.len() - 1);
+        assert_eq!(transcripts(&current), vec!["2"]);
+
+        tree.step_chronologically(2, &mut current);
+        assert_eq!((tree.current_seq(), transcripts(&current)), (5, vec!["main 0"]));
+    }
+
+    #[test]
+    fn history_round_trips_and_rejects_other_clips() {
+        let mut tree = UndoTree::default();
+        let mut current = clips(&["a"]);
+        edit(&mut tree, &mut current, &["b"], None);
+        tree.undo(&mut current);
+        edit(&mut tree, &mut current, &["c"], None);
+
+        let mut restored = UndoTree::from_history(tree.to_history(), &current);
+        assert_eq!(restored.branches().len(), 2);
+        restored.undo(&mut current);
+        assert_eq!(transcripts(&current), vec!["a"]);
+
+        let restored = UndoTree::from_history(tree.to_history(), &clips(&["unrelated"]));
+        assert!(restored.is_empty());
+    }
+}
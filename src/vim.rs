@@ -1,34 +1,406 @@
-use crate::app::App;
+use std::ops::Range;
+use arboard::Clipboard;
+use crate::{
+    app::{App, Clip, InfoPanel, Mode},
+    cache,
+    editor::{InsertField, InsertSession, LineEditor},
+    keys::{Motion, Operator, MAX_COUNT},
+    sox,
+    undo::{self, Coalesce},
+};
 
 const ADJUSTMENT_AMOUNT: f64 = 0.05; // 50ms
 
-pub fn delete_clip(app: &mut App) {
+/// The last command that changed the clips, replayed on the current clip
+/// by `.`. Counts are stored as typed so `.` repeats them, and a count given
+/// to `.` replaces them, as in Vim.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RepeatableChange {
+    Delete { motion: Motion, count: Option<usize>, register: Option<char> },
+    Paste { count: usize, register: Option<char>, before: bool },
+    NudgeStart { increase: bool, count: usize },
+    NudgeEnd { increase: bool, count: usize },
+    /// Adjust mode: the first `count` words of the next clip moved up.
+    MoveWords { count: usize },
+    /// Split mode: the clip split after its first `count` words.
+    Split { count: usize },
+    /// `J`: `count` clips joined into one.
+    Join { count: usize },
+    /// Visual mode `>` / `<` over `count` clips.
+    Shift { forward: bool, count: usize },
+    /// `:comment` over `count` clips.
+    Comment { text: String, count: usize },
+    /// Cut mode or `dw`: `count` words cut. In Normal mode `.` cuts from
+    /// the clip's first word, like `dw`; in Cut mode from the word cursor.
+    CutWords { count: usize },
+}
+
+/// `.`: replays the last change starting at the current clip. Each repeat
+/// is its own undo step, even for nudges that would otherwise merge.
+pub fn repeat_last_change(app: &mut App, count: Option<usize>) {
+    app.undo_tree.seal();
+    let Some(change) = app.last_change.clone() else {
+        app.status_message = "No previous change to repeat.".to_string();
+        return;
+    };
+    let current = app.current_clip_index;
+    match change {
+        RepeatableChange::Delete { motion, count: original, register } => {
+            operate(app, Operator::Delete, motion, count.or(original), register);
+        }
+        RepeatableChange::Paste { count: original, register, before } => {
+            paste_clip(app, count.unwrap_or(original), register, before);
+        }
+        RepeatableChange::NudgeStart { increase, count: original } => {
+            adjust_start_time(app, increase, count.unwrap_or(original));
+        }
+        RepeatableChange::NudgeEnd { increase, count: original } => {
+            adjust_end_time(app, increase, count.unwrap_or(original));
+        }
+        RepeatableChange::MoveWords { count: original } => {
+            crate::autofix::move_words_from_next_clip(app, count.unwrap_or(original));
+        }
+        RepeatableChange::Split { count: original } => {
+            crate::autofix::split_current_clip(app, count.unwrap_or(original));
+        }
+        RepeatableChange::Join { count: original } => join_clips(app, count.unwrap_or(original)),
+        RepeatableChange::Shift { forward, count: original } => {
+            shift_range(app, current..current + count.unwrap_or(original), forward);
+        }
+        RepeatableChange::Comment { text, count: original } => {
+            comment_range(app, current..current + count.unwrap_or(original), text);
+        }
+        RepeatableChange::CutWords { count: original } => {
+            // Like `dw`, this ignores any word cursor left over from Cut mode.
+            crate::cut::cut_words(app, 0, count.unwrap_or(original));
+        }
+    }
+    app.undo_tree.seal();
+}
+
+fn delete_range(app: &mut App, range: Range<usize>, register: Option<char>) {
+    let range = range.start..range.end.min(app.clips.len());
+    if range.is_empty() {
+        return;
+    }
+    let count = range.len();
+    let deleted = app.replace_clips(range.start, count, Vec::new());
+    app.registers.delete(register, deleted);
+    app.current_clip_index = range.start.min(app.clips.len().saturating_sub(1));
+    app.status_message = format!("{} deleted.", clip_count(count));
+}
+
+fn yank_range(app: &mut App, range: Range<usize>, register: Option<char>) {
+    let range = range.start..range.end.min(app.clips.len());
+    if range.is_empty() {
+        return;
+    }
+    app.registers.yank(register, app.clips[range.clone()].to_vec());
+    app.current_clip_index = range.start;
+    app.status_message = format!("{} yanked.", clip_count(range.len()));
+}
+
+/// `p` / `P`: pastes a register `count` times after or before the
+/// current clip.
+pub fn paste_clip(app: &mut App, count: usize, register: Option<char>, before: bool) {
+    if app.registers.get(register).is_empty() {
+        app.status_message = format!("Register {} is empty.", register_label(register));
+        return;
+    }
+    let pasted = app.registers.get(register).len().saturating_mul(count);
+    if pasted > MAX_COUNT {
+        app.status_message = format!("Refusing to paste {} clips at once (at most {}).", pasted, MAX_COUNT);
+        return;
+    }
+    let paste_index = if app.clips.is_empty() || before { app.current_clip_index } else { app.current_clip_index + 1 };
+    let clips_to_paste: Vec<Clip> = (0..count).flat_map(|_| pasted_clips(app, register)).collect();
+    app.replace_clips(paste_index, 0, clips_to_paste);
+    app.current_clip_index = paste_index;
+    app.status_message = format!("{} pasted.", clip_count(pasted));
+    app.last_change = Some(RepeatableChange::Paste { count, register, before });
+}
+
+fn register_label(register: Option<char>) -> String {
+    format!("\"{}", register.unwrap_or('"'))
+}
+
+/// Moves the cursor by a motion such as `5j`, `gg` or `G`.
+pub fn move_cursor(app: &mut App, motion: Motion, count: Option<usize>) {
     if !app.clips.is_empty() {
-        app.save_undo_state();
-        let deleted_clip = app.clips.remove(app.current_clip_index);
-        app.register = Some(deleted_clip);
-        if app.current_clip_index >= app.clips.len() && !app.clips.is_empty() {
-            app.current_clip_index = app.clips.len() - 1;
+        app.current_clip_index = motion_target(app, motion, count);
+    }
+}
+
+/// Applies an operator linewise over the clips between the cursor and the
+/// motion's target, both included: `dG`, `y3j`, `d}`, `5dd`.
+pub fn operate(app: &mut App, operator: Operator, motion: Motion, count: Option<usize>, register: Option<char>) {
+    if app.clips.is_empty() {
+        return;
+    }
+    if matches!(motion, Motion::WordForward | Motion::WordBackward) {
+        // Without a word cursor, `dw` cuts from the start of the clip.
+        match (operator, motion) {
+            (Operator::Delete, Motion::WordForward) => crate::cut::cut_words(app, 0, count.unwrap_or(1).max(1)),
+            _ => app.status_message = "Only dw works on words; use x or v to pick words to cut.".to_string(),
         }
-        app.status_message = "1 clip deleted.".to_string();
+        return;
     }
+    let current = app.current_clip_index;
+    let range = if motion == Motion::Line {
+        current..current + count.unwrap_or(1).max(1)
+    } else {
+        let target = motion_target(app, motion, count);
+        current.min(target)..current.max(target) + 1
+    };
+    match operator {
+        Operator::Delete => {
+            delete_range(app, range, register);
+            app.last_change = Some(RepeatableChange::Delete { motion, count, register });
+        }
+        Operator::Yank => yank_range(app, range, register),
+    }
+}
+
+fn motion_target(app: &App, motion: Motion, count: Option<usize>) -> usize {
+    let current = app.current_clip_index;
+    let last = app.clips.len().saturating_sub(1);
+    let steps = count.unwrap_or(1).max(1);
+    match motion {
+        Motion::Down => current.saturating_add(steps).min(last),
+        Motion::Up => current.saturating_sub(steps),
+        Motion::First => count.map_or(0, |n| n.saturating_sub(1)).min(last),
+        Motion::Last => count.map_or(last, |n| n.saturating_sub(1)).min(last),
+        Motion::Line => current.saturating_add(steps - 1).min(last),
+        Motion::WordForward | Motion::WordBackward => current,
+        Motion::SpeakerRunForward => {
+            let mut index = current;
+            for step in 0..steps {
+                if step > 0 && index < last {
+                    index += 1;
+                }
+                while index < last && app.clips[index + 1].speaker == app.clips[index].speaker {
+                    index += 1;
+                }
+            }
+            index
+        }
+        Motion::SpeakerRunBackward => {
+            let mut index = current;
+            for step in 0..steps {
+                if step > 0 && index > 0 {
+                    index -= 1;
+                }
+                while index > 0 && app.clips[index - 1].speaker == app.clips[index].speaker {
+                    index -= 1;
+                }
+            }
+            index
+        }
+    }
+}
+
+/// A register's clips, marked as manual edits.
+fn pasted_clips(app: &App, register: Option<char>) -> Vec<Clip> {
+    app.registers.get(register).iter().cloned()
+        .map(|mut clip| {
+            clip.is_manually_adjusted = true; // Pasted clips are considered manual
+            clip
+        })
+        .collect()
+}
+
+fn clip_count(count: usize) -> String {
+    if count == 1 { "1 clip".to_string() } else { format!("{} clips", count) }
+}
+
+/// Gaps between joined clips longer than this are reported, since the
+/// joined clip now plays the audio in between.
+const JOIN_GAP_WARNING_SECONDS: f64 = 1.0;
+
+/// `J` / `{count}J`: merges `count` clips (at least two) starting at the
+/// current one. Transcripts and comments are concatenated and the result
+/// spans from the first start to the last end. Clips by different speakers,
+/// or out of audio order, are not joined.
+pub fn join_clips(app: &mut App, count: usize) {
+    let start = app.current_clip_index;
+    let end = start.saturating_add(count.max(2)).min(app.clips.len());
+    if end - start < 2 {
+        app.status_message = "No next clip to join.".to_string();
+        return;
+    }
+    let clips = &app.clips[start..end];
+    if let Some(other) = clips.iter().find(|c| c.speaker != clips[0].speaker) {
+        app.status_message = format!("Cannot join clips from different speakers ({} and {}).", clips[0].speaker, other.speaker);
+        return;
+    }
+    if clips.windows(2).any(|pair| pair[1].start_time < pair[0].start_time) {
+        app.status_message = "Cannot join clips that are out of order in the audio.".to_string();
+        return;
+    }
+    let largest_gap = clips.windows(2).map(|pair| pair[1].start_time - pair[0].end_time).fold(0.0, f64::max);
+
+    let mut joined = clips[0].clone();
+    let keep_timings = clips.iter().all(|c| c.has_word_timings() || c.transcript.trim().is_empty());
+    for clip in &clips[1..] {
+        if !clip.transcript.trim().is_empty() {
+            if !joined.transcript.is_empty() {
+                joined.transcript.push(' ');
+            }
+            joined.transcript.push_str(clip.transcript.trim());
+        }
+        if !clip.comment.is_empty() {
+            if !joined.comment.is_empty() {
+                joined.comment.push_str(" / ");
+            }
+            joined.comment.push_str(&clip.comment);
+        }
+        joined.words.extend(clip.words.iter().cloned());
+        for &cut in &clip.cuts {
+            joined.add_cut(cut);
+        }
+        joined.end_time = joined.end_time.max(clip.end_time);
+    }
+    if !keep_timings {
+        joined.words.clear();
+    }
+    joined.is_manually_adjusted = true;
+
+    let joined_count = end - start;
+    app.replace_clips(start, joined_count, vec![joined]);
+    app.last_change = Some(RepeatableChange::Join { count: joined_count });
+    app.status_message = if largest_gap > JOIN_GAP_WARNING_SECONDS {
+        format!("Joined {}. Note: the joined clip includes a {:.1}s gap of audio.", clip_count(joined_count), largest_gap)
+    } else {
+        format!("Joined {}.", clip_count(joined_count))
+    };
+}
+
+pub fn enter_visual_mode(app: &mut App) {
+    if !app.clips.is_empty() {
+        app.visual_anchor = Some(app.current_clip_index);
+        app.mode = Mode::Visual;
+    }
+}
+
+pub fn exit_visual_mode(app: &mut App) {
+    app.visual_anchor = None;
+    app.mode = Mode::Normal;
+}
+
+pub fn delete_selection(app: &mut App, register: Option<char>) {
+    let range = app.selected_range();
+    let count = range.len();
+    delete_range(app, range, register);
+    app.last_change = Some(RepeatableChange::Delete { motion: Motion::Line, count: Some(count), register });
+    exit_visual_mode(app);
+}
+
+pub fn yank_selection(app: &mut App, register: Option<char>) {
+    yank_range(app, app.selected_range(), register);
+    exit_visual_mode(app);
+}
+
+/// Replaces the selection with a register. The replaced clips go to the
+/// unnamed register, as in Vim.
+pub fn paste_over_selection(app: &mut App, register: Option<char>) {
+    if app.registers.get(register).is_empty() {
+        app.status_message = format!("Register {} is empty.", register_label(register));
+        exit_visual_mode(app);
+        return;
+    }
+    let range = app.selected_range();
+    let clips_to_paste = pasted_clips(app, register);
+    let count = clips_to_paste.len();
+    let replaced = app.replace_clips(range.start, range.len(), clips_to_paste);
+    app.registers.delete(None, replaced);
+    app.current_clip_index = range.start;
+    exit_visual_mode(app);
+    app.status_message = format!("{} pasted.", clip_count(count));
+}
+
+/// `:registers`: the contents of every non-empty register.
+pub fn show_registers(app: &mut App) {
+    let registers = app.registers.list();
+    if registers.is_empty() {
+        app.status_message = "All registers are empty.".to_string();
+        return;
+    }
+    let lines = registers.iter()
+        .map(|(name, clips)| {
+            let first = &clips[0];
+            let mut preview: String = format!("[{}] {}", first.speaker, first.transcript).chars().take(60).collect();
+            if clips.len() > 1 {
+                preview.push_str(" ...");
+            }
+            format!("\"{}  {:>8}  {}", name, clip_count(clips.len()), preview)
+        })
+        .collect();
+    app.info_panel = Some(InfoPanel { title: "Registers".to_string(), lines });
+}
+
+/// `>` / `<` in Visual mode: moves every selected clip's audio window
+/// forward or backward by 50ms.
+pub fn shift_selection(app: &mut App, forward: bool) {
+    shift_range(app, app.selected_range(), forward);
+    exit_visual_mode(app);
 }
 
-pub fn yank_clip(app: &mut App) {
-    if let Some(clip) = app.clips.get(app.current_clip_index) {
-        app.register = Some(clip.clone());
-        app.status_message = "1 clip yanked.".to_string();
+/// Shifts the clips in `range`, as long as they stay clear of their
+/// neighbours and of the start of the file.
+fn shift_range(app: &mut App, range: Range<usize>, forward: bool) {
+    let range = range.start..range.end.min(app.clips.len());
+    if range.is_empty() {
+        return;
     }
+    let adjustment = if forward { ADJUSTMENT_AMOUNT } else { -ADJUSTMENT_AMOUNT };
+    let new_start = app.clips[range.start].start_time + adjustment;
+    let new_end = app.clips[range.end - 1].end_time + adjustment;
+    let prev_end = range.start.checked_sub(1).map(|i| app.clips[i].end_time);
+    let next_start = app.clips.get(range.end).map(|c| c.start_time);
+
+    if new_start < 0.0 || prev_end.is_some_and(|end| new_start < end) || next_start.is_some_and(|start| new_end > start) {
+        app.status_message = "Cannot shift selection: it would overlap a neighbouring clip.".to_string();
+        return;
+    }
+    app.update_clips(range.clone(), |clip| {
+        clip.start_time += adjustment;
+        clip.end_time += adjustment;
+        clip.is_manually_adjusted = true;
+    });
+    app.current_clip_index = range.start;
+    app.status_message = format!("{} shifted {}.", clip_count(range.len()), if forward { "forward" } else { "back" });
+    app.last_change = Some(RepeatableChange::Shift { forward, count: range.len() });
 }
 
-pub fn paste_clip(app: &mut App) {
-    if let Some(clip_to_paste) = app.register.clone() {
-        app.save_undo_state();
-        let paste_index = if app.clips.is_empty() { 0 } else { app.current_clip_index + 1 };
-        app.clips.insert(paste_index, clip_to_paste);
-        app.clips[paste_index].is_manually_adjusted = true; // Pasted clips are considered manual
-        app.current_clip_index = paste_index;
-        app.status_message = "1 clip pasted.".to_string();
+/// `:comment <text>`: sets the comment of every selected clip, or of the
+/// current clip outside Visual mode. An empty text clears it.
+pub fn comment_selection(app: &mut App, text: String) {
+    comment_range(app, app.selected_range(), text);
+}
+
+fn comment_range(app: &mut App, range: Range<usize>, text: String) {
+    let range = range.start..range.end.min(app.clips.len());
+    let count = range.len();
+    app.update_clips(range, |clip| {
+        clip.comment = text.clone();
+        clip.is_manually_adjusted = true;
+    });
+    app.status_message = format!("Comment set on {}.", clip_count(count));
+    app.last_change = Some(RepeatableChange::Comment { text, count });
+}
+
+/// Plays the selected clips back to back.
+pub fn play_selection(app: &mut App) {
+    if let Some(pid) = app.playback_pid.take() {
+        sox::stop_playback(pid);
+        app.status_message = "Playback stopped.".to_string();
+        return;
+    }
+    let range = app.selected_range();
+    app.status_message = format!("Playing {}...", clip_count(range.len()));
+    match sox::play_playlist(app, range) {
+        Ok(child) => app.playback_pid = Some(child.id()),
+        Err(e) => app.status_message = format!("Playback failed: {}", e),
     }
 }
 
@@ -45,95 +417,369 @@ pub fn previous_clip(app: &mut App) {
 }
 
 pub fn undo(app: &mut App) {
-    if let Some(previous_state) = app.undo_stack.pop() {
-        let current_state = app.clips.clone();
-        app.redo_stack.push(current_state);
-        app.clips = previous_state;
+    if let Some(index) = app.undo_tree.undo(&mut app.clips) {
+        app.change_count += 1;
         app.status_message = "Undo.".to_string();
-        if app.current_clip_index >= app.clips.len() {
-            app.current_clip_index = app.clips.len().saturating_sub(1);
-        }
+        app.current_clip_index = index.min(app.clips.len().saturating_sub(1));
     } else {
         app.status_message = "Already at oldest change.".to_string();
     }
 }
 
 pub fn redo(app: &mut App) {
-    if let Some(next_state) = app.redo_stack.pop() {
-        let current_state = app.clips.clone();
-        app.undo_stack.push(current_state);
-        app.clips = next_state;
+    if let Some(index) = app.undo_tree.redo(&mut app.clips) {
+        app.change_count += 1;
         app.status_message = "Redo.".to_string();
-        if app.current_clip_index >= app.clips.len() {
-            app.current_clip_index = app.clips.len().saturating_sub(1);
-        }
+        app.current_clip_index = index.min(app.clips.len().saturating_sub(1));
     } else {
         app.status_message = "Already at newest change.".to_string();
     }
 }
 
+/// `g-` and `g+`: steps through undo states in the order they were made,
+/// crossing into branches that `u` and `Ctrl+r` can't reach.
+pub fn undo_chronologically(app: &mut App, steps: i64) {
+    match app.undo_tree.step_chronologically(steps, &mut app.clips) {
+        Some(index) => after_time_travel(app, index),
+        None if steps < 0 => app.status_message = "Already at oldest change.".to_string(),
+        None => app.status_message = "Already at newest change.".to_string(),
+    }
+}
 
-pub fn adjust_start_time(app: &mut App, increase: bool) {
-    app.save_undo_state();
-    let adjustment = if increase { ADJUSTMENT_AMOUNT } else { -ADJUSTMENT_AMOUNT };
-    
-    let prev_clip_end_time = if app.current_clip_index > 0 {
-        app.clips.get(app.current_clip_index - 1).map(|c| c.end_time)
-    } else { None };
-
-    if let Some(clip) = app.clips.get_mut(app.current_clip_index) {
-        let new_start_time = clip.start_time + adjustment;
-        if new_start_time >= 0.0 && new_start_time < clip.end_time {
-            if let Some(prev_end) = prev_clip_end_time {
-                if new_start_time > prev_end { 
-                    clip.start_time = new_start_time;
-                    clip.is_manually_adjusted = true;
-                }
-            } else { 
-                clip.start_time = new_start_time;
-                clip.is_manually_adjusted = true;
-            }
+/// `:earlier` and `:later`. A bare number counts undo states like `g-`/`g+`;
+/// with a unit (`30s`, `5m`, `2h`, `1d`) it moves by wall-clock time.
+pub fn time_travel(app: &mut App, backwards: bool, amount: Option<String>) {
+    let amount = amount.unwrap_or_else(|| "1".to_string());
+    if let Ok(count) = amount.parse::<i64>() {
+        if count <= 0 {
+            app.status_message = format!("Invalid count: {} (must be at least 1)", amount);
+            return;
         }
+        undo_chronologically(app, if backwards { -count } else { count });
+        return;
+    }
+    let Some(secs) = cache::parse_age(&amount) else {
+        app.status_message = format!("Invalid time: {} (use e.g. 10, 30s, 5m, 2h or 1d)", amount);
+        return;
+    };
+    let current = app.undo_tree.current_time();
+    let target = if backwards { current.saturating_sub(secs) } else { current.saturating_add(secs) };
+    match app.undo_tree.goto_time(target, &mut app.clips) {
+        Some(index) => after_time_travel(app, index),
+        None => app.status_message = "No change at that time.".to_string(),
+    }
+}
+
+fn after_time_travel(app: &mut App, index: usize) {
+    app.change_count += 1;
+    app.current_clip_index = index.min(app.clips.len().saturating_sub(1));
+    app.status_message = format!(
+        "At change #{} ({}).",
+        app.undo_tree.current_seq(),
+        undo::format_age(app.undo_tree.current_time())
+    );
+}
+
+/// `:undolist`: the tip of every undo branch.
+pub fn show_undo_list(app: &mut App) {
+    if app.undo_tree.is_empty() {
+        app.status_message = "Nothing to undo.".to_string();
+        return;
     }
+    let mut lines = vec!["number  changes  when".to_string()];
+    lines.extend(app.undo_tree.branches().iter().map(|branch| {
+        format!(
+            "{:>6}  {:>7}  {}{}",
+            branch.seq,
+            branch.changes,
+            undo::format_age(branch.time),
+            if branch.is_current { "  (current)" } else { "" }
+        )
+    }));
+    app.info_panel = Some(InfoPanel {
+        title: format!("Undo branches (at change #{})", app.undo_tree.current_seq()),
+        lines,
+    });
 }
 
-pub fn adjust_end_time(app: &mut App, increase: bool) {
-    app.save_undo_state();
+
+/// Nudges the start time by `count` steps of 50ms, going as far as it can
+/// while staying after the previous clip's end and before this clip's end.
+/// Repeated nudges of the same clip undo as one step.
+pub fn adjust_start_time(app: &mut App, increase: bool, count: usize) {
+    let index = app.current_clip_index;
+    let Some(clip) = app.clips.get(index) else { return };
     let adjustment = if increase { ADJUSTMENT_AMOUNT } else { -ADJUSTMENT_AMOUNT };
+    let prev_clip_end_time = index.checked_sub(1).and_then(|i| app.clips.get(i)).map(|c| c.end_time);
+    let is_valid = |start: f64| start >= 0.0 && start < clip.end_time && prev_clip_end_time.is_none_or(|end| start > end);
+    let steps = (1..=count).take_while(|&step| is_valid(clip.start_time + step as f64 * adjustment)).last();
 
-    let next_clip_start_time = if app.current_clip_index < app.clips.len() - 1 {
-        app.clips.get(app.current_clip_index + 1).map(|c| c.start_time)
-    } else { None };
-
-    if let Some(clip) = app.clips.get_mut(app.current_clip_index) {
-        let new_end_time = clip.end_time + adjustment;
-        if new_end_time > clip.start_time {
-            if let Some(next_start) = next_clip_start_time {
-                if new_end_time < next_start { 
-                    clip.end_time = new_end_time; 
-                    clip.is_manually_adjusted = true;
-                }
-            } else { 
-                clip.end_time = new_end_time;
-                clip.is_manually_adjusted = true;
-            }
+    if let Some(steps) = steps {
+        let new_start_time = clip.start_time + steps as f64 * adjustment;
+        app.update_clip(index, Some(Coalesce::StartTime(index)), |clip| {
+            clip.start_time = new_start_time;
+            clip.is_manually_adjusted = true;
+        });
+    }
+    app.last_change = Some(RepeatableChange::NudgeStart { increase, count });
+}
+
+/// Nudges the end time by `count` steps of 50ms, going as far as it can
+/// while staying after this clip's start and before the next clip's start.
+pub fn adjust_end_time(app: &mut App, increase: bool, count: usize) {
+    let index = app.current_clip_index;
+    let Some(clip) = app.clips.get(index) else { return };
+    let adjustment = if increase { ADJUSTMENT_AMOUNT } else { -ADJUSTMENT_AMOUNT };
+    let next_clip_start_time = app.clips.get(index + 1).map(|c| c.start_time);
+    let is_valid = |end: f64| end > clip.start_time && next_clip_start_time.is_none_or(|start| end < start);
+    let steps = (1..=count).take_while(|&step| is_valid(clip.end_time + step as f64 * adjustment)).last();
+
+    if let Some(steps) = steps {
+        let new_end_time = clip.end_time + steps as f64 * adjustment;
+        app.update_clip(index, Some(Coalesce::EndTime(index)), |clip| {
+            clip.end_time = new_end_time;
+            clip.is_manually_adjusted = true;
+        });
+    }
+    app.last_change = Some(RepeatableChange::NudgeEnd { increase, count });
+}
+
+/// `i` / `I`: starts editing the current clip's comment or transcript.
+pub fn enter_insert_mode(app: &mut App, field: InsertField) {
+    let Some(clip) = app.clips.get(app.current_clip_index) else { return };
+    app.insert = Some(InsertSession::new(clip, app.current_clip_index, field));
+    app.undo_tree.seal();
+    app.mode = Mode::Insert;
+}
+
+pub fn exit_insert_mode(app: &mut App) {
+    app.insert = None;
+    app.undo_tree.seal();
+    app.mode = Mode::Normal;
+}
+
+/// `Tab` in Insert mode: moves between the transcript and the comment.
+pub fn switch_insert_field(app: &mut App) {
+    if let Some(session) = app.insert.as_mut() {
+        if let Some(clip) = app.clips.get(session.clip_index) {
+            session.switch_field(clip);
         }
     }
 }
 
-pub fn append_to_comment(app: &mut App, c: char) {
-    app.save_undo_state();
-    if let Some(clip) = app.clips.get_mut(app.current_clip_index) {
-        clip.comment.push(c);
-        clip.is_manually_adjusted = true;
+/// Moves the Insert mode cursor. The clip is left alone.
+pub fn move_text_cursor(app: &mut App, motion: impl FnOnce(&mut LineEditor)) {
+    if let Some(session) = app.insert.as_mut() {
+        motion(&mut session.editor);
     }
 }
 
-pub fn pop_from_comment(app: &mut App) {
-    app.save_undo_state();
-    if let Some(clip) = app.clips.get_mut(app.current_clip_index) {
-        clip.comment.pop();
-        clip.is_manually_adjusted = true;
+/// Runs an edit on the Insert mode text and, if the text changed, writes
+/// the result to the clip. All edits in one session share an undo step.
+pub fn edit_text(app: &mut App, edit: impl FnOnce(&mut LineEditor)) {
+    let Some(mut session) = app.insert.take() else { return };
+    let before = session.editor.text();
+    edit(&mut session.editor);
+    if session.editor.text() != before {
+        let index = session.clip_index;
+        app.update_clip(index, Some(Coalesce::Insert(index)), |clip| session.apply(clip));
+    }
+    app.insert = Some(session);
+}
+
+/// `Ctrl+v` in Insert mode: inserts the system clipboard's text.
+pub fn paste_from_clipboard(app: &mut App) {
+    match Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+        Ok(text) => edit_text(app, |editor| editor.insert_str(&text)),
+        Err(e) => app.status_message = format!("Failed to read clipboard: {}", e),
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{test_clips, transcripts};
+
+    fn app_with(texts: &[&str]) -> App {
+        let mut app = App::new("audio.wav".to_string(), None, false);
+        app.clips = test_clips(texts);
+        app
+    }
+
+    #[test]
+    fn earlier_and_later_take_positive_counts_only() {
+        let mut app = app_with(&["a"]);
+        app.set_clips(test_clips(&["b"]));
+        app.set_clips(test_clips(&["c"]));
+
+        for count in ["0", "-1", "-9223372036854775808"] {
+            time_travel(&mut app, true, Some(count.to_string()));
+            assert!(app.status_message.starts_with("Invalid count"), "{}", count);
+            assert_eq!(transcripts(&app.clips), vec!["c"]);
+        }
+
+        time_travel(&mut app, true, Some("9223372036854775807".to_string()));
+        assert_eq!(transcripts(&app.clips), vec!["a"]);
+        time_travel(&mut app, false, Some("1".to_string()));
+        assert_eq!(transcripts(&app.clips), vec!["b"]);
+        time_travel(&mut app, false, Some("99999999999999999999d".to_string()));
+        assert!(app.status_message.starts_with("Invalid time"));
+    }
+
+    /// Visual mode from `anchor` to `cursor`, either way round.
+    fn select(app: &mut App, anchor: usize, cursor: usize) {
+        app.current_clip_index = anchor;
+        enter_visual_mode(app);
+        app.current_clip_index = cursor;
+    }
+
+    #[test]
+    fn reversed_selection_deletes_the_same_clips() {
+        let mut app = app_with(&["a", "b", "c", "d"]);
+        select(&mut app, 2, 1);
+        delete_selection(&mut app, None);
+        assert_eq!(transcripts(&app.clips), vec!["a", "d"]);
+        assert_eq!(transcripts(app.registers.get(None)), vec!["b", "c"]);
+        assert_eq!((app.current_clip_index, app.mode, app.visual_anchor), (1, Mode::Normal, None));
+    }
+
+    #[test]
+    fn deleting_a_selection_that_ends_at_the_last_clip() {
+        let mut app = app_with(&["a", "b", "c"]);
+        select(&mut app, 2, 1);
+        delete_selection(&mut app, None);
+        assert_eq!(transcripts(&app.clips), vec!["a"]);
+        assert_eq!(app.current_clip_index, 0);
+
+        select(&mut app, 0, 0);
+        delete_selection(&mut app, None);
+        assert!(app.clips.is_empty());
+        assert_eq!(app.current_clip_index, 0);
+    }
+
+    #[test]
+    fn reversed_selection_yanks_in_clip_order() {
+        let mut app = app_with(&["a", "b", "c"]);
+        select(&mut app, 2, 0);
+        yank_selection(&mut app, None);
+        assert_eq!(transcripts(app.registers.get(None)), vec!["a", "b", "c"]);
+        assert_eq!(transcripts(&app.clips), vec!["a", "b", "c"]);
+        assert_eq!(app.current_clip_index, 0);
+    }
+
+    #[test]
+    fn pasting_over_a_reversed_selection_at_the_end_swaps_with_the_register() {
+        let mut app = app_with(&["a", "b", "c"]);
+        app.registers.yank(None, test_clips(&["x"]));
+        select(&mut app, 2, 1);
+        paste_over_selection(&mut app, None);
+        assert_eq!(transcripts(&app.clips), vec!["a", "x"]);
+        assert_eq!(transcripts(app.registers.get(None)), vec!["b", "c"]);
+        assert_eq!(app.current_clip_index, 1);
+
+        undo(&mut app);
+        assert_eq!(transcripts(&app.clips), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn paste_puts_a_named_register_before_or_after() {
+        let mut app = app_with(&["a", "b"]);
+        app.registers.yank(Some('q'), test_clips(&["x", "y"]));
+        paste_clip(&mut app, 1, Some('q'), true);
+        assert_eq!(transcripts(&app.clips), vec!["x", "y", "a", "b"]);
+        assert_eq!(app.current_clip_index, 0);
+
+        app.current_clip_index = 3;
+        paste_clip(&mut app, 2, Some('q'), false);
+        assert_eq!(transcripts(&app.clips), vec!["x", "y", "a", "b", "x", "y", "x", "y"]);
+        assert_eq!(app.current_clip_index, 4);
+
+        paste_clip(&mut app, 1, Some('z'), false);
+        assert_eq!(app.status_message, "Register \"z is empty.");
+    }
+
+    #[test]
+    fn counted_nudges_stop_at_the_neighbours_and_undo_at_once() {
+        let mut app = app_with(&["a", "b"]);
+        app.clips[0].end_time = 0.5;
+        app.clips[1].start_time = 1.0;
+        app.clips[1].end_time = 2.0;
+        app.current_clip_index = 1;
+
+        adjust_start_time(&mut app, false, 5);
+        assert!((app.clips[1].start_time - 0.75).abs() < 1e-9);
+        adjust_start_time(&mut app, false, 1000);
+        assert!((app.clips[1].start_time - 0.55).abs() < 1e-9);
+        adjust_end_time(&mut app, true, MAX_COUNT);
+        assert!((app.clips[1].end_time - 502.0).abs() < 1e-6);
+
+        undo(&mut app);
+        assert_eq!(app.clips[1].end_time, 2.0);
+        undo(&mut app);
+        assert_eq!(app.clips[1].start_time, 1.0);
+    }
+
+    #[test]
+    fn each_repeat_is_its_own_undo_step() {
+        let mut app = app_with(&["a"]);
+        adjust_end_time(&mut app, true, 2);
+        repeat_last_change(&mut app, None);
+        repeat_last_change(&mut app, None);
+        assert!((app.clips[0].end_time - 1.3).abs() < 1e-9);
+
+        undo(&mut app);
+        assert!((app.clips[0].end_time - 1.2).abs() < 1e-9);
+        undo(&mut app);
+        assert!((app.clips[0].end_time - 1.1).abs() < 1e-9);
+        undo(&mut app);
+        assert_eq!(app.clips[0].end_time, 1.0);
+    }
+
+    #[test]
+    fn huge_join_counts_stop_at_the_last_clip() {
+        let mut app = app_with(&["a", "b", "c"]);
+        for (i, clip) in app.clips.iter_mut().enumerate() {
+            clip.start_time = i as f64;
+            clip.end_time = i as f64 + 1.0;
+        }
+        app.current_clip_index = 1;
+        join_clips(&mut app, usize::MAX);
+        assert_eq!(transcripts(&app.clips), vec!["a", "b c"]);
+        assert_eq!((app.clips[1].start_time, app.clips[1].end_time), (1.0, 3.0));
+
+        join_clips(&mut app, 2);
+        assert_eq!(app.status_message, "No next clip to join.");
+        assert_eq!(app.clips.len(), 2);
+    }
+
+    #[test]
+    fn joining_keeps_word_timings_only_when_every_clip_has_them() {
+        let mut app = app_with(&[]);
+        app.clips = vec![Clip::for_test("a b", 0.0, 2.0), Clip::for_test("c", 2.0, 3.0)];
+        join_clips(&mut app, 2);
+        let words: Vec<_> = app.clips[0].words.iter().map(|w| (w.text.as_str(), w.start, w.end)).collect();
+        assert_eq!(words, vec![("a", 0.0, 1.0), ("b", 1.0, 2.0), ("c", 2.0, 3.0)]);
+
+        let mut app = app_with(&["a", "b"]);
+        app.clips[1].words.clear();
+        join_clips(&mut app, 2);
+        assert_eq!(transcripts(&app.clips), vec!["a b"]);
+        assert!(app.clips[0].words.is_empty());
+    }
+
+    #[test]
+    fn normal_mode_dw_and_its_repeat_cut_from_the_first_word() {
+        let mut app = app_with(&[]);
+        app.clips = vec![Clip::for_test("a b c d e f", 0.0, 6.0)];
+        app.adjust_word_index = 3;
+        operate(&mut app, Operator::Delete, Motion::WordForward, None, None);
+        assert_eq!(transcripts(&app.clips), vec!["b c d e f"]);
+
+        app.adjust_word_index = 2;
+        repeat_last_change(&mut app, None);
+        assert_eq!(transcripts(&app.clips), vec!["c d e f"]);
+        repeat_last_change(&mut app, Some(2));
+        assert_eq!(transcripts(&app.clips), vec!["e f"]);
+        assert_eq!(app.clips[0].audible_segments(), vec![(4.0, 6.0)]);
+    }
+}
@@ -0,0 +1,331 @@
+use serde::Deserialize;
+use std::{env, fs};
+use tokio::process::Command;
+use crate::{app::{Clip, Word}, transcriber::{retry_after_header, ChunkContext, TranscribeError, TranscribeFuture, Transcriber}};
+
+/// Consecutive segments are merged into one clip while the gap between them
+/// stays under this many seconds and the speaker does not change.
+const MAX_PAUSE_SECONDS: f64 = 0.8;
+const MAX_CLIP_SECONDS: f64 = 30.0;
+
+/// whisper.cpp `-ojf` output.
+#[derive(Deserialize, Debug)] pub struct CliOutput { pub transcription: Vec<CliSegment> }
+#[derive(Deserialize, Debug)] pub struct CliSegment { pub offsets: CliOffsets, pub text: String, #[serde(default)] pub tokens: Vec<CliToken>, #[serde(default)] pub speaker_turn_next: bool }
+#[derive(Deserialize, Debug)] pub struct CliToken { pub text: String, pub offsets: CliOffsets, #[serde(default)] pub p: Option<f64> }
+#[derive(Deserialize, Debug)] pub struct CliOffsets { pub from: u64, pub to: u64 }
+
+/// whisper.cpp server (and OpenAI-compatible servers) `verbose_json` output.
+#[derive(Deserialize, Debug)] pub struct ServerOutput { pub segments: Vec<ServerSegment> }
+#[derive(Deserialize, Debug)] pub struct ServerSegment { pub start: f64, pub end: f64, pub text: String, #[serde(default)] pub words: Vec<ServerWord> }
+#[derive(Deserialize, Debug)] pub struct ServerWord { pub word: String, pub start: f64, pub end: f64, #[serde(default)] pub probability: Option<f64> }
+
+struct Segment {
+    start: f64,
+    end: f64,
+    text: String,
+    words: Vec<Word>,
+    speaker_turn_next: bool,
+}
+
+/// Runs a local whisper.cpp binary (`whisper-cli`) on each chunk. Nothing
+/// leaves the machine.
+pub struct WhisperCliTranscriber {
+    binary: String,
+    model_path: String,
+    diarize: bool,
+}
+
+impl WhisperCliTranscriber {
+    pub fn new(binary: String, model_path: String, diarize: bool) -> Self {
+        WhisperCliTranscriber { binary, model_path, diarize }
+    }
+
+    async fn run(&self, audio_data: &[u8], context: ChunkContext) -> Result<Vec<Clip>, TranscribeError> {
+        // Per process, so two avim instances never share chunk files.
+        let temp_dir = env::temp_dir().join(format!("avim_whisper_{}", std::process::id()));
+        fs::create_dir_all(&temp_dir)?;
+        let input_path = temp_dir.join(format!("chunk_{}.wav", context.index));
+        let output_prefix = temp_dir.join(format!("chunk_{}", context.index));
+        let output_path = temp_dir.join(format!("chunk_{}.json", context.index));
+        fs::write(&input_path, audio_data)?;
+
+        let mut cmd = Command::new(&self.binary);
+        cmd.arg("-m").arg(&self.model_path)
+            .arg("-f").arg(&input_path)
+            .arg("-ojf")
+            .arg("-of").arg(&output_prefix)
+            .arg("-np");
+        if self.diarize {
+            cmd.arg("-tdrz");
+        }
+        let output = cmd.output().await
+            .map_err(|e| format!("Failed to run whisper binary '{}': {}", self.binary, e))?;
+        fs::remove_file(&input_path).ok();
+
+        if !output.status.success() {
+            return Err(TranscribeError::Fatal(format!("whisper exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim())));
+        }
+
+        let file_contents = fs::read_to_string(&output_path)?;
+        fs::remove_file(&output_path).ok();
+        let parsed: CliOutput = serde_json::from_str(&file_contents)
+            .map_err(|e| format!("Failed to parse whisper JSON output: {}", e))?;
+
+        let segments = parsed.transcription.into_iter()
+            .map(|s| Segment {
+                start: s.offsets.from as f64 / 1000.0,
+                end: s.offsets.to as f64 / 1000.0,
+                text: s.text,
+                words: tokens_to_words(&s.tokens),
+                speaker_turn_next: s.speaker_turn_next,
+            })
+            .collect();
+        Ok(segments_to_clips(segments))
+    }
+}
+
+impl Transcriber for WhisperCliTranscriber {
+    fn name(&self) -> &str {
+        "whisper"
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("whisper/{}/diarize={}", self.model_path, self.diarize)
+    }
+
+    fn transcribe<'a>(&'a self, audio_data: &'a [u8], context: ChunkContext) -> TranscribeFuture<'a> {
+        Box::pin(self.run(audio_data, context))
+    }
+}
+
+/// Talks to a whisper server listening on the local machine, e.g. the
+/// whisper.cpp `server` example at `http://127.0.0.1:8080/inference`.
+pub struct WhisperServerTranscriber {
+    url: String,
+}
+
+impl WhisperServerTranscriber {
+    pub fn new(url: String) -> Self {
+        WhisperServerTranscriber { url }
+    }
+
+    async fn run(&self, audio_data: &[u8]) -> Result<Vec<Clip>, TranscribeError> {
+        let file_part = reqwest::multipart::Part::bytes(audio_data.to_vec())
+            .file_name("chunk.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| TranscribeError::Fatal(e.to_string()))?;
+        let form = reqwest::multipart::Form::new()
+            .part("file", file_part)
+            .text("response_format", "verbose_json");
+
+        let client = reqwest::Client::new();
+        let res = client.post(&self.url).multipart(form).send().await
+            .map_err(|e| TranscribeError::retryable(format!("Failed to reach whisper server at {}: {}", self.url, e)))?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let retry_after = retry_after_header(res.headers());
+            let message = format!("Whisper server error ({}): {}", status, res.text().await.unwrap_or_default());
+            return Err(TranscribeError::from_status(status, retry_after, message));
+        }
+
+        let parsed: ServerOutput = res.json().await
+            .map_err(|e| format!("Failed to parse whisper server response: {}", e))?;
+
+        let segments = parsed.segments.into_iter()
+            .map(|s| Segment {
+                start: s.start,
+                end: s.end,
+                text: s.text,
+                words: s.words.into_iter()
+                    .map(|w| Word { text: w.word.trim().to_string(), start: w.start, end: w.end, confidence: w.probability })
+                    .filter(|w| !w.text.is_empty())
+                    .collect(),
+                speaker_turn_next: false,
+            })
+            .collect();
+        Ok(segments_to_clips(segments))
+    }
+}
+
+impl Transcriber for WhisperServerTranscriber {
+    fn name(&self) -> &str {
+        "whisper-server"
+    }
+
+    fn fingerprint(&self) -> String {
+        format!("whisper-server/{}", self.url)
+    }
+
+    fn transcribe<'a>(&'a self, audio_data: &'a [u8], _context: ChunkContext) -> TranscribeFuture<'a> {
+        Box::pin(self.run(audio_data))
+    }
+}
+
+/// Whisper produces short, sentence-sized segments without speaker labels.
+/// Group them into avim clips, starting a new clip at long pauses and at
+/// speaker turns reported by tinydiarize.
+fn segments_to_clips(segments: Vec<Segment>) -> Vec<Clip> {
+    let mut clips: Vec<Clip> = Vec::new();
+    let mut speaker = 1;
+    let mut turn_pending = false;
+
+    for segment in segments {
+        let text = segment.text.trim();
+        if text.is_empty() || text.starts_with('[') {
+            continue;
+        }
+
+        let continues_clip = clips.last().is_some_and(|clip| {
+            !turn_pending
+                && segment.start - clip.end_time < MAX_PAUSE_SECONDS
+                && segment.end - clip.start_time < MAX_CLIP_SECONDS
+        });
+
+        if turn_pending {
+            speaker = if speaker == 1 { 2 } else { 1 };
+        }
+
+        if continues_clip {
+            let clip = clips.last_mut().unwrap();
+            clip.transcript.push(' ');
+            clip.transcript.push_str(text);
+            clip.end_time = segment.end;
+            clip.words.extend(segment.words);
+        } else {
+            clips.push(Clip {
+                id: clips.len(),
+                speaker: format!("Speaker {}", speaker),
+                transcript: text.to_string(),
+                start_time: segment.start,
+                end_time: segment.end,
+                comment: String::new(),
+                is_manually_adjusted: false,
+                words: segment.words,
+                cuts: Vec::new(),
+            });
+        }
+
+        turn_pending = segment.speaker_turn_next;
+    }
+
+    clips
+}
+
+/// whisper.cpp reports sub-word tokens. A token that begins with a space
+/// starts a new word; anything else is glued onto the previous one. Special
+/// tokens such as `[_BEG_]` are skipped.
+fn tokens_to_words(tokens: &[CliToken]) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut probabilities: Vec<Vec<f64>> = Vec::new();
+
+    for token in tokens {
+        if token.text.starts_with("[_") || token.text.trim().is_empty() {
+            continue;
+        }
+        let start = token.offsets.from as f64 / 1000.0;
+        let end = token.offsets.to as f64 / 1000.0;
+
+        if token.text.starts_with(' ') || words.is_empty() {
+            words.push(Word { text: token.text.trim().to_string(), start, end, confidence: None });
+            probabilities.push(Vec::new());
+        } else {
+            let word = words.last_mut().unwrap();
+            word.text.push_str(token.text.trim());
+            word.end = end;
+        }
+        if let Some(p) = token.p {
+            probabilities.last_mut().unwrap().push(p);
+        }
+    }
+
+    for (word, p) in words.iter_mut().zip(probabilities) {
+        if !p.is_empty() {
+            word.confidence = Some(p.iter().sum::<f64>() / p.len() as f64);
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f64, end: f64, text: &str) -> Segment {
+        Segment { start, end, text: text.to_string(), words: Vec::new(), speaker_turn_next: false }
+    }
+
+    #[test]
+    fn empty_response_gives_no_clips() {
+        assert!(segments_to_clips(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn blank_and_bracketed_segments_are_skipped() {
+        let clips = segments_to_clips(vec![
+            segment(0.0, 1.0, "[BLANK_AUDIO]"),
+            segment(1.0, 2.0, "   "),
+            segment(2.0, 3.0, " Hello there."),
+        ]);
+        assert_eq!(clips.len(), 1);
+        assert_eq!(clips[0].transcript, "Hello there.");
+        assert_eq!(clips[0].start_time, 2.0);
+    }
+
+    #[test]
+    fn short_pauses_join_and_long_pauses_split() {
+        let clips = segments_to_clips(vec![
+            segment(0.0, 1.0, "One."),
+            segment(1.5, 2.0, "Two."),
+            segment(4.0, 5.0, "Three."),
+        ]);
+        assert_eq!(clips.len(), 2);
+        assert_eq!(clips[0].transcript, "One. Two.");
+        assert_eq!(clips[0].end_time, 2.0);
+        assert_eq!(clips[1].transcript, "Three.");
+        assert_eq!(clips[1].id, 1);
+    }
+
+    #[test]
+    fn speaker_turns_start_a_clip_for_the_other_speaker() {
+        let mut first = segment(0.0, 1.0, "Question?");
+        first.speaker_turn_next = true;
+        let clips = segments_to_clips(vec![first, segment(1.1, 2.0, "Answer.")]);
+        assert_eq!(clips.len(), 2);
+        assert_eq!(clips[0].speaker, "Speaker 1");
+        assert_eq!(clips[1].speaker, "Speaker 2");
+    }
+
+    fn token(text: &str, from: u64, to: u64, p: Option<f64>) -> CliToken {
+        CliToken { text: text.to_string(), offsets: CliOffsets { from, to }, p }
+    }
+
+    #[test]
+    fn tokens_glue_into_words_and_punctuation_sticks_to_the_previous_word() {
+        let words = tokens_to_words(&[
+            token("[_BEG_]", 0, 0, None),
+            token(" Hel", 0, 200, Some(0.8)),
+            token("lo", 200, 400, Some(0.6)),
+            token(",", 400, 450, Some(1.0)),
+            token(" world", 500, 900, None),
+            token(".", 900, 950, None),
+        ]);
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, vec!["Hello,", "world."]);
+        assert_eq!((words[0].start, words[0].end), (0.0, 0.45));
+        assert!((words[0].confidence.unwrap() - 0.8).abs() < 1e-9);
+        assert_eq!(words[1].confidence, None);
+    }
+
+    #[test]
+    fn segments_without_token_timestamps_have_no_words() {
+        let output: CliOutput = serde_json::from_str(
+            r#"{"transcription": [{"offsets": {"from": 0, "to": 1000}, "text": " Hi."}]}"#,
+        ).unwrap();
+        assert!(tokens_to_words(&output.transcription[0].tokens).is_empty());
+
+        let clips = segments_to_clips(vec![segment(0.0, 1.0, "Hi.")]);
+        assert!(clips[0].words.is_empty());
+        assert!(!clips[0].has_word_timings());
+    }
+}